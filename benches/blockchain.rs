@@ -0,0 +1,76 @@
+// Consultas feitas pelos handlers com a trava da blockchain segura. Cada
+// grupo roda com cadeias de tamanhos diferentes: com os índices, o tempo de
+// cada consulta não deve crescer junto com a cadeia.
+//
+//   cargo bench --bench blockchain
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ed25519_dalek::SigningKey;
+use rust_crud_api::blockchain::blockchain::Blockchain;
+use rust_crud_api::blockchain::election::{Eligibility, NewElection, VoteOption, VotingMethod};
+
+const ELECTIONS: usize = 20;
+const CHAIN_SIZES: [usize; 3] = [1_000, 10_000, 50_000];
+
+// Cadeia com `ELECTIONS` eleições e `votes` votos: cada eleitor vota em todas
+// as eleições, então o número de votos por eleitor é o mesmo em todo tamanho
+fn build_blockchain(votes: usize) -> (Blockchain, Vec<String>) {
+    let mut blockchain = Blockchain::new(SigningKey::from_bytes(&[7; 32]));
+    let options = ["a", "b", "c"];
+
+    let mut election_ids = Vec::new();
+    for election in 0..ELECTIONS {
+        let slug = format!("bench-{}", election);
+        let block = blockchain.create_election(NewElection {
+            slug: Some(slug.clone()),
+            title: Some(format!("Eleição {}", election)),
+            description: None,
+            options: options.iter().map(|option| VoteOption::from_id(option.to_string())).collect(),
+            starts_at: None,
+            ends_at: None,
+            voting_method: VotingMethod::Plurality,
+            max_selections: None,
+            eligibility: Eligibility::default(),
+            secret_ballot: false,
+        }, "creator@bench.com".to_string()).unwrap();
+        blockchain.commit_block(block);
+        election_ids.push(blockchain.resolve_election_id(&slug).unwrap());
+    }
+
+    for vote in 0..votes {
+        let voter_id = format!("voter{}@bench.com", vote / ELECTIONS);
+        let election_id = election_ids[vote % ELECTIONS].clone();
+        let ballot = vec![options[vote % options.len()].to_string()];
+        let (block, _) = blockchain.add_vote_operation(voter_id, election_id, ballot).unwrap();
+        blockchain.commit_block(block);
+    }
+
+    (blockchain, election_ids)
+}
+
+fn lookups(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookups");
+
+    for votes in CHAIN_SIZES {
+        let (blockchain, election_ids) = build_blockchain(votes);
+        let voter_id = "voter0@bench.com";
+        let election_id = &election_ids[ELECTIONS / 2];
+
+        group.bench_with_input(BenchmarkId::new("get_votes_by_user", votes), &votes, |b, _| {
+            b.iter(|| blockchain.get_votes_by_user(black_box(voter_id), black_box(election_id)))
+        });
+        group.bench_with_input(BenchmarkId::new("get_elections_by_user", votes), &votes, |b, _| {
+            b.iter(|| blockchain.get_elections_by_user(black_box(voter_id)))
+        });
+        group.bench_with_input(BenchmarkId::new("running_tally", votes), &votes, |b, _| {
+            b.iter(|| blockchain.running_tally(black_box(election_id)).cloned())
+        });
+        group.bench_with_input(BenchmarkId::new("resolve_election_id", votes), &votes, |b, _| {
+            b.iter(|| blockchain.resolve_election_id(black_box("bench-10")))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lookups);
+criterion_main!(benches);
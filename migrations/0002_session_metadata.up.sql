@@ -0,0 +1,6 @@
+-- Metadados para listar e revogar sessões individualmente
+ALTER TABLE sessions ADD COLUMN user_agent VARCHAR;
+ALTER TABLE sessions ADD COLUMN ip_address VARCHAR;
+ALTER TABLE sessions ADD COLUMN expires_at TIMESTAMP;
+
+CREATE INDEX sessions_user_id_idx ON sessions (user_id);
@@ -0,0 +1,18 @@
+-- Refresh tokens opacos, guardados só como hash. Tokens de uma mesma cadeia de
+-- rotação compartilham `family_id`; reutilizar um token já trocado revoga a família.
+CREATE TABLE refresh_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id),
+    family_id VARCHAR NOT NULL,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
+CREATE INDEX refresh_tokens_user_id_idx ON refresh_tokens (user_id);
+
+ALTER TABLE sessions ADD COLUMN family_id VARCHAR;
+CREATE INDEX sessions_family_id_idx ON sessions (family_id);
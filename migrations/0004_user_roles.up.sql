@@ -0,0 +1,4 @@
+-- Papéis de acesso. Todo usuário existente começa como eleitor; o primeiro
+-- admin é criado pela linha de comando (--make-admin).
+ALTER TABLE users ADD COLUMN role VARCHAR NOT NULL DEFAULT 'voter'
+    CHECK (role IN ('admin', 'manager', 'voter'));
@@ -0,0 +1,10 @@
+-- Quem já votou em eleições com voto secreto. A cédula fica na cadeia sem
+-- identificação; esta tabela só impede o voto duplo. A linha é gravada na
+-- mesma transação do bloco da cédula, então quem tem acesso direto ao banco
+-- consegue ligar as duas pelas colunas de sistema (xmin, ctid) ou pelo WAL.
+-- O sigilo vale para quem usa a API e a cadeia, não para o administrador.
+CREATE TABLE election_participation (
+    election_id VARCHAR NOT NULL,
+    voter_id VARCHAR NOT NULL,
+    PRIMARY KEY (election_id, voter_id)
+);
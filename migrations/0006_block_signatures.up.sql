@@ -0,0 +1,3 @@
+-- Assinatura Ed25519 de cada bloco. Blocos anteriores ficam nulos até a
+-- execução de --sign-chain.
+ALTER TABLE blocks ADD COLUMN signature VARCHAR;
@@ -0,0 +1,4 @@
+-- Não desfaz a separação: devolver as tabelas antigas ao lugar deixaria o
+-- servidor sem conseguir carregar a cadeia. As tabelas legacy_* continuam
+-- disponíveis para consulta.
+SELECT 1;
@@ -0,0 +1,35 @@
+-- Bancos criados na primeira versão da blockchain guardavam cada voto em
+-- colunas próprias (voter_id, election_id, vote_option_id) e as eleições na
+-- tabela `elections`. O hash desses blocos foi calculado sobre outro formato,
+-- então eles não podem ser convertidos para blocos com payload sem quebrar a
+-- cadeia. As tabelas antigas são preservadas como legacy_* e a cadeia
+-- recomeça do gênesis na próxima subida do servidor.
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'blocks' AND column_name = 'voter_id'
+    ) THEN
+        ALTER TABLE blocks RENAME TO legacy_blocks;
+        ALTER TABLE legacy_blocks RENAME CONSTRAINT blocks_pkey TO legacy_blocks_pkey;
+        ALTER TABLE legacy_blocks RENAME CONSTRAINT blocks_hash_key TO legacy_blocks_hash_key;
+
+        CREATE TABLE blocks (
+            index BIGINT PRIMARY KEY,
+            timestamp BIGINT NOT NULL,
+            payload JSONB NOT NULL,
+            previous_hash VARCHAR NOT NULL,
+            hash VARCHAR NOT NULL UNIQUE,
+            signature VARCHAR
+        );
+
+        RAISE NOTICE 'Legacy vote blocks moved to legacy_blocks; the chain restarts from genesis';
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM information_schema.tables
+        WHERE table_schema = current_schema() AND table_name = 'elections'
+    ) THEN
+        ALTER TABLE elections RENAME TO legacy_elections;
+    END IF;
+END $$;
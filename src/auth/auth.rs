@@ -0,0 +1,181 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use deadpool_postgres::{GenericClient, Pool};
+use jsonwebtoken::{decode, encode, Validation, DecodingKey, Header, EncodingKey};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Sha256, Digest};
+use uuid::Uuid;
+
+use crate::config::config::config;
+use crate::errors::errors::AppError;
+use crate::models::models::{Claims, Role};
+
+// Usuário autenticado na requisição. Só existe se o JWT do header
+// Authorization tiver assinatura válida, não estiver expirado e ainda
+// corresponder a uma sessão ativa na tabela `sessions`.
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+    pub email: String,
+    pub token: String,
+    pub role: Role,
+}
+
+impl AuthenticatedUser {
+    pub fn require_role(&self, allowed: &[Role]) -> Result<(), AppError> {
+        if allowed.contains(&self.role) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden)
+        }
+    }
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Result<String, AppError> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .map(|token| token.to_string())
+        .ok_or(AppError::MissingToken)
+}
+
+pub fn decode_token(token: &str) -> Result<Claims, AppError> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config().jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .map_err(|_| AppError::InvalidToken)
+}
+
+pub async fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, AppError> {
+    let token = bearer_token(req)?;
+    let claims = decode_token(&token)?;
+
+    let pool = req.app_data::<web::Data<Pool>>()
+        .ok_or_else(|| AppError::Internal("Database pool not configured".to_string()))?;
+    let client = pool.get().await?;
+
+    // Um token assinado só vale enquanto a sessão dele existir
+    let row = client.query_opt(
+        "SELECT users.id, users.email, users.role FROM sessions
+         JOIN users ON users.id = sessions.user_id
+         WHERE sessions.token = $1",
+        &[&token],
+    ).await?.ok_or(AppError::InvalidToken)?;
+
+    // Se o papel mudou depois da emissão, o token deixa de valer e o cliente
+    // precisa renová-lo (o refresh emite um token com o papel atual)
+    let email: String = row.get(1);
+    let role: String = row.get(2);
+    if email != claims.sub || role != claims.role.as_str() {
+        return Err(AppError::InvalidToken);
+    }
+
+    Ok(AuthenticatedUser {
+        user_id: row.get(0),
+        email,
+        token,
+        role: claims.role,
+    })
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate(&req).await })
+    }
+}
+
+#[derive(Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+// Refresh tokens são opacos: 32 bytes aleatórios em hexadecimal.
+// No banco fica apenas o hash SHA-256.
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn hash_refresh_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// Emite um novo access token (JWT registrado em `sessions`) e um novo refresh
+// token, ambos pertencentes à família `family_id`
+pub async fn issue_tokens<C: GenericClient>(
+    client: &C,
+    req: &HttpRequest,
+    user_id: i32,
+    email: &str,
+    role: Role,
+    family_id: &str,
+) -> Result<TokenPair, AppError> {
+    let config = config();
+
+    // Gerar token JWT
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as usize;
+    let claims = Claims {
+        sub: email.to_string(),
+        iat: now,
+        exp: now + config.token_lifetime_secs as usize,
+        jti: Uuid::new_v4().to_string(),
+        role,
+    };
+
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(config.jwt_secret.as_bytes()))
+        .map_err(|e| AppError::Internal(format!("Token generation error: {}", e)))?;
+
+    let user_agent = req.headers()
+        .get("User-Agent")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let ip_address = req.connection_info().realip_remote_addr().map(|addr| addr.to_string());
+
+    // Inserir nova sessão
+    client.execute(
+        "INSERT INTO sessions (user_id, token, user_agent, ip_address, family_id, expires_at)
+         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP + make_interval(secs => $6))",
+        &[&user_id, &token, &user_agent, &ip_address, &family_id, &(config.token_lifetime_secs as f64)],
+    ).await?;
+
+    let refresh_token = generate_refresh_token();
+    client.execute(
+        "INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+         VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))",
+        &[&user_id, &family_id, &hash_refresh_token(&refresh_token), &(config.refresh_token_lifetime_secs as f64)],
+    ).await?;
+
+    Ok(TokenPair { token, refresh_token })
+}
+
+// Revoga todos os refresh tokens da família e encerra as sessões abertas por ela
+pub async fn revoke_family<C: GenericClient>(client: &C, family_id: &str) -> Result<(), AppError> {
+    client.execute(
+        "UPDATE refresh_tokens SET revoked_at = CURRENT_TIMESTAMP
+         WHERE family_id = $1 AND revoked_at IS NULL",
+        &[&family_id],
+    ).await?;
+
+    client.execute(
+        "DELETE FROM sessions WHERE family_id = $1",
+        &[&family_id],
+    ).await?;
+
+    Ok(())
+}
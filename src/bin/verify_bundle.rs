@@ -0,0 +1,238 @@
+// Verificador offline de pacotes de auditoria exportados por
+// GET /election/{id}/export. Não precisa de banco nem do servidor: confere
+// hashes, assinaturas, raiz de Merkle e apuração só com o conteúdo do pacote.
+//
+// A chave pública vem no próprio pacote, e quem adultera o pacote pode trocá-la.
+// Passe em `--public-key` a chave publicada em GET /chain/pubkey: o pacote é
+// conferido contra ela e recusado se trouxer outra.
+//
+// Uso: verify_bundle [--public-key <hex>] <arquivo.audit.jsonl>
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use rust_crud_api::blockchain::audit::{BundleHeader, BundleLine, BundleMerkleRoot, ElectionDefinition, BUNDLE_FORMAT, BUNDLE_VERSION};
+use rust_crud_api::blockchain::block::{calculate_hash, Block, BlockPayload};
+use rust_crud_api::blockchain::merkle::merkle_root;
+use rust_crud_api::blockchain::receipt::ballot_commitment;
+use rust_crud_api::blockchain::tally::{tally, Tally};
+
+#[derive(Default)]
+struct Bundle {
+    header: Option<BundleHeader>,
+    election: Option<ElectionDefinition>,
+    blocks: Vec<Block>,
+    tally: Option<Tally>,
+    merkle_root: Option<BundleMerkleRoot>,
+    signature: Option<String>,
+    // Bytes cobertos pela assinatura: tudo antes da linha da assinatura
+    signed_len: usize,
+}
+
+fn parse_bundle(contents: &str) -> Result<Bundle, String> {
+    let mut bundle = Bundle::default();
+    let mut offset = 0;
+
+    for (number, line) in contents.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        if line.trim().is_empty() {
+            continue;
+        }
+        if bundle.signature.is_some() {
+            return Err(format!("line {}: content after the signature line", number + 1));
+        }
+
+        let parsed: BundleLine = serde_json::from_str(line)
+            .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        match parsed {
+            BundleLine::Header(header) => bundle.header = Some(header),
+            BundleLine::Election(election) => bundle.election = Some(election),
+            BundleLine::Block(block) => bundle.blocks.push(block),
+            BundleLine::Tally(tally) => bundle.tally = Some(tally),
+            BundleLine::MerkleRoot(root) => bundle.merkle_root = Some(root),
+            BundleLine::Signature(signature) => {
+                bundle.signature = Some(signature);
+                bundle.signed_len = start;
+            }
+        }
+    }
+
+    Ok(bundle)
+}
+
+fn parse_public_key(value: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("public key is not 32 bytes of hex")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("invalid public key: {}", e))
+}
+
+// Confere o pacote inteiro; retorna a lista de problemas encontrados
+fn verify(contents: &str, expected_key: Option<&VerifyingKey>) -> Result<Vec<String>, String> {
+    let bundle = parse_bundle(contents)?;
+    let header = bundle.header.as_ref().ok_or("missing header line")?;
+    let election = bundle.election.as_ref().ok_or("missing election line")?;
+    let reported_tally = bundle.tally.as_ref().ok_or("missing tally line")?;
+    let reported_root = bundle.merkle_root.as_ref().ok_or("missing merkle_root line")?;
+    let signature = bundle.signature.as_ref().ok_or("missing signature line")?;
+
+    if header.format != BUNDLE_FORMAT || header.version != BUNDLE_VERSION {
+        return Err(format!("unsupported bundle format {} v{}", header.format, header.version));
+    }
+    let bundle_key = parse_public_key(&header.public_key)?;
+
+    let mut problems = Vec::new();
+
+    // Com a chave esperada, é ela que confere as assinaturas
+    let public_key = match expected_key {
+        Some(expected_key) if *expected_key != bundle_key => {
+            problems.push(format!(
+                "bundle public key {} does not match the expected key {}",
+                hex::encode(bundle_key.to_bytes()),
+                hex::encode(expected_key.to_bytes())
+            ));
+            *expected_key
+        }
+        Some(expected_key) => *expected_key,
+        None => bundle_key,
+    };
+
+    // Assinatura do pacote
+    let bundle_signature = hex::decode(signature).ok().and_then(|bytes| Signature::from_slice(&bytes).ok());
+    match bundle_signature {
+        Some(bundle_signature) if public_key.verify(&contents.as_bytes()[..bundle.signed_len], &bundle_signature).is_ok() => {}
+        _ => problems.push("bundle signature does not match the public key".to_string()),
+    }
+
+    // Cada bloco: hash recalculado, assinatura e eleição
+    let mut previous_index = None;
+    for block in &bundle.blocks {
+        if calculate_hash(block.index, block.timestamp, &block.payload, &block.previous_hash) != block.hash {
+            problems.push(format!("block {}: hash does not match block contents", block.index));
+        }
+        if !block.has_valid_signature(&public_key) {
+            problems.push(format!("block {}: missing or invalid signature", block.index));
+        }
+        if block.payload.election_id() != Some(&header.election_id) {
+            problems.push(format!("block {}: belongs to another election", block.index));
+        }
+        if previous_index.is_some_and(|previous| block.index <= previous) {
+            problems.push(format!("block {}: blocks out of order", block.index));
+        }
+        previous_index = Some(block.index);
+    }
+
+    // A definição tem que ser a mesma do bloco que criou a eleição
+    let created: Vec<ElectionDefinition> = bundle.blocks
+        .iter()
+        .filter_map(|block| ElectionDefinition::from_payload(&block.payload))
+        .collect();
+    match created.as_slice() {
+        [definition] if definition == election => {}
+        [_] => problems.push("election line does not match the election_created block".to_string()),
+        _ => problems.push(format!("expected one election_created block, found {}", created.len())),
+    }
+
+    // Raiz de Merkle recalculada a partir das cédulas
+    let ballots: Vec<Vec<String>> = bundle.blocks.iter().filter_map(|block| block.payload.ballot()).collect();
+    let commitments: Vec<String> = bundle.blocks
+        .iter()
+        .filter(|block| block.payload.ballot().is_some())
+        .map(|block| ballot_commitment(&block.payload))
+        .collect();
+    let root = merkle_root(&commitments);
+    if root != reported_root.root || commitments.len() != reported_root.ballot_count {
+        problems.push("merkle_root line does not match the ballots in the bundle".to_string());
+    }
+    let published = bundle.blocks.iter().find_map(|block| match &block.payload {
+        BlockPayload::ElectionClosed { ballots_root, .. } => ballots_root.clone(),
+        _ => None,
+    });
+    if let Some(published) = published {
+        if published != root {
+            problems.push("root published when the election closed does not match the ballots".to_string());
+        }
+    }
+
+    // Apuração recalculada a partir das cédulas
+    let recomputed = tally(election.voting_method, &election.vote_options, &ballots);
+    if recomputed != *reported_tally {
+        problems.push(format!(
+            "tally does not match the ballots: recomputed {}",
+            serde_json::to_string(&recomputed).unwrap_or_default()
+        ));
+    }
+
+    println!("Election:  {}", election.election_id);
+    match expected_key {
+        Some(_) => println!("Key:       {} (expected)", hex::encode(public_key.to_bytes())),
+        None => println!("Key:       {} (from the bundle; compare it with GET /chain/pubkey or pass --public-key)", hex::encode(public_key.to_bytes())),
+    }
+    println!("Blocks:    {}", bundle.blocks.len());
+    println!("Ballots:   {}", ballots.len());
+    println!("Root:      {}", root);
+    println!("Winners:   {}", recomputed.winners.join(", "));
+
+    Ok(problems)
+}
+
+const USAGE: &str = "Usage: verify_bundle [--public-key <hex>] <bundle.audit.jsonl>";
+
+// Retorna (chave esperada, caminho do pacote)
+fn parse_args() -> Result<(Option<VerifyingKey>, String), String> {
+    let mut expected_key = None;
+    let mut path = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--public-key" => {
+                let value = args.next().ok_or("--public-key needs a value")?;
+                expected_key = Some(parse_public_key(value.trim())?);
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    Ok((expected_key, path.ok_or("missing bundle path")?))
+}
+
+fn main() -> ExitCode {
+    let (expected_key, path) = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match verify(&contents, expected_key.as_ref()) {
+        Ok(problems) if problems.is_empty() => {
+            println!("Bundle is valid");
+            ExitCode::SUCCESS
+        }
+        Ok(problems) => {
+            for problem in problems {
+                println!("FAIL: {}", problem);
+            }
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("Malformed bundle: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
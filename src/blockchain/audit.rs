@@ -0,0 +1,89 @@
+use serde::{Serialize, Deserialize};
+use crate::blockchain::block::{Block, BlockPayload};
+use crate::blockchain::election::{VoteOption, VotingMethod};
+use crate::blockchain::tally::Tally;
+
+pub const BUNDLE_FORMAT: &str = "election-audit-bundle";
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Pacote de auditoria de uma eleição, em JSON lines, uma `BundleLine` por linha:
+//
+//   {"header": ...}       formato, eleição, horário da exportação e chave pública
+//   {"election": ...}     definição da eleição, igual à do bloco election_created
+//   {"block": ...}        cada bloco da eleição, em ordem, com hash e assinatura
+//   {"tally": ...}        apuração calculada pelo servidor
+//   {"merkle_root": ...}  raiz de Merkle das cédulas
+//   {"signature": "..."}  assinatura Ed25519 sobre os bytes de todas as linhas
+//                         anteriores, incluindo as quebras de linha
+//
+// O pacote se confere sozinho com src/bin/verify_bundle.rs.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleLine {
+    Header(BundleHeader),
+    Election(ElectionDefinition),
+    Block(Block),
+    Tally(Tally),
+    MerkleRoot(BundleMerkleRoot),
+    Signature(String),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleHeader {
+    pub format: String,
+    pub version: u32,
+    pub election_id: String,
+    pub exported_at: u128,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ElectionDefinition {
+    pub election_id: String,
+    pub creator_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    pub vote_options: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<VoteOption>,
+    pub voting_method: VotingMethod,
+    pub max_selections: Option<u32>,
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+    pub secret_ballot: bool,
+}
+
+impl ElectionDefinition {
+    pub fn from_payload(payload: &BlockPayload) -> Option<Self> {
+        match payload {
+            BlockPayload::ElectionCreated {
+                election_id, creator_id, slug, vote_options, title, description, options, starts_at, ends_at, voting_method, max_selections, secret_ballot, ..
+            } => Some(ElectionDefinition {
+                election_id: election_id.clone(),
+                creator_id: creator_id.clone(),
+                slug: slug.clone(),
+                vote_options: vote_options.clone(),
+                title: title.clone(),
+                description: description.clone(),
+                options: options.clone(),
+                voting_method: *voting_method,
+                max_selections: *max_selections,
+                starts_at: *starts_at,
+                ends_at: *ends_at,
+                secret_ballot: *secret_ballot,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BundleMerkleRoot {
+    pub root: String,
+    pub published: bool,
+    pub ballot_count: usize,
+}
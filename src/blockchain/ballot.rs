@@ -0,0 +1,17 @@
+use rand::RngCore;
+use sha2::{Sha256, Digest};
+
+// Em eleições com voto secreto a cadeia guarda só o `ballot_id`, derivado de
+// um segredo aleatório que fica com o eleitor. Sem o segredo não há como
+// ligar a cédula a quem votou; com ele o eleitor reencontra o próprio voto.
+pub fn generate_ballot_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn ballot_id(ballot_secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(ballot_secret.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
@@ -1,31 +1,162 @@
 use serde::{Serialize, Deserialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use sha2::{Sha256, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use crate::blockchain::election::{Eligibility, VoteOption, VotingMethod};
 
-#[derive(Serialize, Deserialize, Debug)]
+// Evento registrado em cada bloco. Todo o estado da blockchain
+// (eleições, criadores, votantes) é reconstruído reaplicando esses eventos.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockPayload {
+    Genesis,
+    ElectionCreated {
+        election_id: String,
+        creator_id: String,
+        // Nome legível e único escolhido pelo criador; o id é gerado pelo servidor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        slug: Option<String>,
+        // Ids das opções, na ordem de exibição (ordenados nas eleições antigas)
+        vote_options: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        // Rótulo, descrição e imagem de cada opção, na mesma ordem de
+        // `vote_options`; ausente nas eleições criadas antes dos rótulos
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        options: Vec<VoteOption>,
+        // u64 porque o serde não lê u128 dentro de enums com tag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        starts_at: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ends_at: Option<u64>,
+        #[serde(default, skip_serializing_if = "VotingMethod::is_plurality")]
+        voting_method: VotingMethod,
+        // Limite de opções marcadas numa eleição por aprovação (sem limite se ausente)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_selections: Option<u32>,
+        // Lista de eleitores aptos; ausente quando a eleição é aberta a todos
+        #[serde(default, skip_serializing_if = "Eligibility::is_open")]
+        eligibility: Eligibility,
+        // Voto secreto: as cédulas entram como `BallotCast`, sem o eleitor
+        #[serde(default, skip_serializing_if = "is_false")]
+        secret_ballot: bool,
+    },
+    VoteCast {
+        voter_id: String,
+        election_id: String,
+        vote_option_id: String,
+        // Cédula completa: em ordem de preferência nas eleições ranqueadas, ou
+        // as opções aprovadas (ordenadas) nas eleições por aprovação.
+        // Em eleições de maioria simples fica vazia e vale `vote_option_id`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        vote_option_ids: Vec<String>,
+    },
+    // Cédula de uma eleição com voto secreto. `ballot_id` é o hash de um
+    // segredo que só o eleitor conhece (ver blockchain::ballot)
+    BallotCast {
+        election_id: String,
+        ballot_id: String,
+        vote_option_id: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        vote_option_ids: Vec<String>,
+    },
+    ElectionClosed {
+        election_id: String,
+        closed_by: String,
+        // Raiz de Merkle das cédulas da eleição no momento do encerramento
+        // (ver blockchain::merkle); ausente em encerramentos antigos
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ballots_root: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ballot_count: Option<u64>,
+    },
+    // Eleitores incluídos ou retirados da lista antes da abertura da eleição
+    EligibilityChanged {
+        election_id: String,
+        changed_by: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        added: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        removed: Vec<String>,
+    },
+}
+
+impl BlockPayload {
+    // Eleição a que o evento se refere; None para o gênesis
+    pub fn election_id(&self) -> Option<&String> {
+        match self {
+            BlockPayload::Genesis => None,
+            BlockPayload::ElectionCreated { election_id, .. }
+            | BlockPayload::VoteCast { election_id, .. }
+            | BlockPayload::BallotCast { election_id, .. }
+            | BlockPayload::ElectionClosed { election_id, .. }
+            | BlockPayload::EligibilityChanged { election_id, .. } => Some(election_id),
+        }
+    }
+
+    // Cédula completa registrada no bloco, se ele for um voto
+    pub fn ballot(&self) -> Option<Vec<String>> {
+        match self {
+            BlockPayload::VoteCast { vote_option_id, vote_option_ids, .. }
+            | BlockPayload::BallotCast { vote_option_id, vote_option_ids, .. } => {
+                if vote_option_ids.is_empty() {
+                    Some(vec![vote_option_id.clone()])
+                } else {
+                    Some(vote_option_ids.clone())
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
     pub index: u64,
     pub timestamp: u128,
-    pub voter_id: String,
-    pub election_id: String,
-    pub vote_option_id: String,
+    pub payload: BlockPayload,
     pub previous_hash: String,
     pub hash: String,
+    // Assinatura Ed25519 do servidor sobre `hash`, em hexadecimal. Fica fora
+    // do hash, então blocos antigos podem ser assinados depois (--sign-chain).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 impl Block {
-    pub fn new(index: u64, voter_id: String, election_id: String, vote_option_id: String, previous_hash: String) -> Self {
-        let timestamp = current_timestamp();
-        let hash = calculate_hash(index, timestamp, &voter_id, &election_id, &vote_option_id, &previous_hash);
+    pub fn new(index: u64, timestamp: u128, payload: BlockPayload, previous_hash: String) -> Self {
+        let hash = calculate_hash(index, timestamp, &payload, &previous_hash);
 
         Block {
             index,
             timestamp,
-            voter_id,
-            election_id,
-            vote_option_id,
+            payload,
             previous_hash,
             hash,
+            signature: None,
+        }
+    }
+
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        let signature = signing_key.sign(self.hash.as_bytes());
+        self.signature = Some(hex::encode(signature.to_bytes()));
+    }
+
+    // Confere a assinatura do bloco; false se ela faltar ou não bater com a chave
+    pub fn has_valid_signature(&self, verifying_key: &VerifyingKey) -> bool {
+        let bytes = match self.signature.as_deref().map(hex::decode) {
+            Some(Ok(bytes)) => bytes,
+            _ => return false,
+        };
+        match Signature::from_slice(&bytes) {
+            Ok(signature) => verifying_key.verify(self.hash.as_bytes(), &signature).is_ok(),
+            Err(_) => false,
         }
     }
 }
@@ -36,8 +167,8 @@ pub fn current_timestamp() -> u128 {
     since_the_epoch.as_millis()
 }
 
-pub fn calculate_hash(index: u64, timestamp: u128, voter_id: &str, election_id: &str, vote_option_id: &str, previous_hash: &str) -> String {
-    let data = (index, timestamp, voter_id, election_id, vote_option_id, previous_hash);
+pub fn calculate_hash(index: u64, timestamp: u128, payload: &BlockPayload, previous_hash: &str) -> String {
+    let data = (index, timestamp, payload, previous_hash);
     let encoded = bincode::serialize(&data).unwrap();
     let mut hasher = Sha256::new();
     hasher.update(&encoded);
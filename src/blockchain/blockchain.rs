@@ -1,117 +1,1246 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{HashSet, HashMap};
-use crate::blockchain::block::Block;
+use std::fmt;
+use std::collections::{BTreeSet, HashSet, HashMap};
+use serde::Serialize;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use uuid::Uuid;
+use crate::errors::errors::AppError;
+use crate::blockchain::ballot::{ballot_id, generate_ballot_secret};
+use crate::blockchain::block::{Block, BlockPayload, calculate_hash, current_timestamp};
+use crate::blockchain::receipt::{ballot_commitment, ReceiptVerification};
+use crate::blockchain::merkle::{merkle_root, MerkleTree, ProofStep};
+use crate::blockchain::tally::{tally, RunningTally};
+use crate::blockchain::index::{ElectionFilter, ElectionIndex, ElectionSort};
+use crate::blockchain::audit::{BundleHeader, BundleLine, BundleMerkleRoot, ElectionDefinition, BUNDLE_FORMAT, BUNDLE_VERSION};
+use crate::blockchain::election::{normalize_email, Election, Eligibility, ElectionSchedule, ElectionStatus, NewElection, VoteOption, VotingMethod};
+
+// `closed_by` dos encerramentos registrados pelo servidor quando a eleição
+// termina pelo horário
+pub const SCHEDULED_CLOSER: &str = "schedule";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainErrorReason {
+    MissingGenesis,
+    InvalidHash,
+    BrokenLink,
+    NonMonotonicIndex,
+    NonMonotonicTimestamp,
+    MerkleRootMismatch,
+    MissingSignature,
+    InvalidSignature,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainError {
+    pub index: u64,
+    pub reason: ChainErrorReason,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.reason {
+            ChainErrorReason::MissingGenesis => "missing or malformed genesis block",
+            ChainErrorReason::InvalidHash => "hash does not match block contents",
+            ChainErrorReason::BrokenLink => "previous_hash does not match the previous block",
+            ChainErrorReason::NonMonotonicIndex => "index out of sequence",
+            ChainErrorReason::NonMonotonicTimestamp => "timestamp earlier than the previous block",
+            ChainErrorReason::MerkleRootMismatch => "published Merkle root does not match the election's ballots",
+            ChainErrorReason::MissingSignature => "block is not signed",
+            ChainErrorReason::InvalidSignature => "signature does not match the server key",
+        };
+        write!(f, "block {}: {}", self.index, reason)
+    }
+}
+
+// Prova de inclusão de uma cédula na árvore de Merkle da sua eleição
+#[derive(Debug, Serialize)]
+pub struct BallotProof {
+    pub election_id: String,
+    pub block_hash: String,
+    pub ballot_commitment: String,
+    pub leaf_index: usize,
+    pub ballot_count: usize,
+    pub proof: Vec<ProofStep>,
+    pub root: String,
+    // A raiz foi publicada no encerramento; senão é provisória e muda a cada voto
+    pub published: bool,
+}
 
 #[derive(Debug)]
 pub struct Blockchain {
     pub chain: Vec<Block>,
     pub voters: HashMap<String, HashSet<String>>,
-    pub elections: HashMap<String, HashSet<String>>,
-    pub creators: HashMap<String, HashSet<String>>  
+    pub elections: HashMap<String, Election>,
+    // Slug -> id da eleição
+    pub slugs: HashMap<String, String>,
+    // Eleições de cada criador, na ordem em que foram criadas
+    pub creators: HashMap<String, Vec<String>>,
+    pub schedules: HashMap<String, ElectionSchedule>,
+    pub methods: HashMap<String, VotingMethod>,
+    pub max_selections: HashMap<String, u32>,
+    pub eligibility: HashMap<String, Eligibility>,
+    pub secret_ballots: HashSet<String>,
+    pub merkle_roots: HashMap<String, String>,
+    pub index: ElectionIndex,
+    // Índices dos blocos, mantidos em `commit_block`, para que as consultas
+    // não percorram a cadeia inteira. Guardam posições em `chain`, que só cresce.
+    block_positions: HashMap<String, usize>,
+    // Blocos de cada eleição, na ordem da cadeia
+    election_blocks: HashMap<String, Vec<usize>>,
+    // Voto aberto de cada eleitor, por eleição
+    votes_by_voter: HashMap<String, HashMap<String, usize>>,
+    // Cédula secreta de cada ballot_id
+    ballots_by_id: HashMap<String, usize>,
+    tallies: HashMap<String, RunningTally>,
+    // Árvore de Merkle das cédulas de cada eleição, e a folha de cada cédula
+    // pela posição do bloco
+    merkle_trees: HashMap<String, MerkleTree>,
+    leaf_indexes: HashMap<usize, usize>,
+    // Resultado da verificação da cadeia, atualizado a cada bloco anexado em
+    // `commit_block`: o primeiro bloco quebrado, se houver
+    integrity: Result<(), ChainError>,
+    // Chave que assina cada bloco novo; a pública confere a cadeia
+    signing_key: SigningKey,
 }
 
 impl Blockchain {
-    pub fn new() -> Self {
-        let mut blockchain = Blockchain {
-            chain: Vec::new(),
-            voters: HashMap::new(),
-            elections: HashMap::new(),
-            creators: HashMap::new(), 
-        };
+    pub fn new(signing_key: SigningKey) -> Self {
+        let mut blockchain = Blockchain::empty(signing_key);
 
         // Criar o bloco gênesis
-        let genesis_block = Block::new(0, String::from(""), String::from(""), String::from(""), String::from("0"));
+        let mut genesis_block = Block::new(0, current_timestamp(), BlockPayload::Genesis, String::from("0"));
+        genesis_block.sign(&blockchain.signing_key);
         blockchain.chain.push(genesis_block);
 
         blockchain
     }
 
-    pub fn create_election(&mut self, election_id: String, vote_options: HashSet<String>, creator_id: String) -> Result<(), String> {
-        if self.elections.contains_key(&election_id) {
-            return Err("Election already exists".to_string());
+    fn empty(signing_key: SigningKey) -> Self {
+        Blockchain {
+            chain: Vec::new(),
+            voters: HashMap::new(),
+            elections: HashMap::new(),
+            slugs: HashMap::new(),
+            creators: HashMap::new(),
+            schedules: HashMap::new(),
+            methods: HashMap::new(),
+            max_selections: HashMap::new(),
+            eligibility: HashMap::new(),
+            secret_ballots: HashSet::new(),
+            merkle_roots: HashMap::new(),
+            index: ElectionIndex::default(),
+            block_positions: HashMap::new(),
+            election_blocks: HashMap::new(),
+            votes_by_voter: HashMap::new(),
+            ballots_by_id: HashMap::new(),
+            tallies: HashMap::new(),
+            merkle_trees: HashMap::new(),
+            leaf_indexes: HashMap::new(),
+            integrity: Ok(()),
+            signing_key,
         }
+    }
 
-        if self.creators.contains_key(&creator_id) {
-            self.creators
-                .entry(creator_id.to_string())
-                .or_insert_with(HashSet::new)
-                .insert(election_id.to_string());
-        } else {
-            self.creators.insert(creator_id.clone(), [election_id.to_string()].iter().cloned().collect());
+    // Reconstrói a blockchain reaplicando os blocos persistidos.
+    // A cadeia é verificada enquanto é reaplicada e recusada se estiver quebrada.
+    pub fn from_stored(blocks: Vec<Block>, signing_key: SigningKey) -> Result<Self, String> {
+        let blockchain = Blockchain::replay(blocks, signing_key);
+
+        if let Err(err) = blockchain.integrity() {
+            if err.reason == ChainErrorReason::MissingSignature {
+                return Err(format!("Stored chain is invalid: {} (run --sign-chain once to sign blocks written before signing was enabled)", err));
+            }
+            return Err(format!("Stored chain is invalid: {}", err));
+        }
+
+        Ok(blockchain)
+    }
+
+    // Reaplica os blocos; quebras na cadeia ficam em `integrity`, sem impedir nada
+    pub fn replay(blocks: Vec<Block>, signing_key: SigningKey) -> Self {
+        let mut blockchain = Blockchain::empty(signing_key);
+
+        for block in blocks {
+            blockchain.commit_block(block);
         }
 
+        blockchain
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    pub fn verifying_key_hex(&self) -> String {
+        hex::encode(self.verifying_key().to_bytes())
+    }
+
+    // Percorre a cadeia inteira recalculando os hashes, conferindo o
+    // encadeamento e a assinatura de cada bloco; retorna o primeiro bloco
+    // quebrado encontrado
+    pub fn verify(&self) -> Result<(), ChainError> {
+        self.check(true)
+    }
+
+    // Resultado da verificação mantida a cada bloco anexado. Barato, ao
+    // contrário de `verify`, que percorre a cadeia e confere cada assinatura.
+    pub fn integrity(&self) -> Result<(), &ChainError> {
+        self.integrity.as_ref().map(|_| ())
+    }
+
+    // Como `verify`, mas aceita blocos ainda sem assinatura (as que existirem
+    // precisam ser válidas). Usado para assinar a cadeia antiga.
+    pub fn verify_unsigned(&self) -> Result<(), ChainError> {
+        self.check(false)
+    }
+
+    fn check(&self, require_signatures: bool) -> Result<(), ChainError> {
+        check_chain(&self.chain, &self.signing_key.verifying_key(), require_signatures)
+    }
+
+    // Confere o bloco que está para ser anexado contra o último da cadeia
+    pub fn check_next(&self, block: &Block) -> Result<(), ChainError> {
+        check_block(self.chain.last(), self.chain.len(), block, &self.signing_key.verifying_key(), true)?;
+
+        if let BlockPayload::ElectionClosed { election_id, ballots_root: Some(root), .. } = &block.payload {
+            check_root(block, root, &self.ballots_root(election_id))?;
+        }
 
-        self.elections.insert(election_id, vote_options);
         Ok(())
     }
 
-    pub fn add_vote_operation(&mut self, voter_id: String, election_id: String, vote_option_id: String) -> Result<(), String> {
-        if let Some(vote_options) = self.elections.get(&election_id) {
-            if !vote_options.contains(&vote_option_id) {
-                return Err("Vote option does not exist in this election".to_string());
+    fn next_block(&self, payload: BlockPayload) -> Block {
+        let last_block = self.chain.last().unwrap();
+        // O relógio pode voltar atrás; o horário de um bloco nunca é anterior ao do último
+        let timestamp = current_timestamp().max(last_block.timestamp);
+        let mut block = Block::new(last_block.index + 1, timestamp, payload, last_block.hash.clone());
+        block.sign(&self.signing_key);
+        block
+    }
+
+    // Assina os blocos gravados antes da assinatura existir; retorna os que mudaram
+    pub fn sign_unsigned_blocks(&mut self) -> Vec<&Block> {
+        let unsigned: Vec<usize> = self.chain
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.signature.is_none())
+            .map(|(position, _)| position)
+            .collect();
+
+        for &position in &unsigned {
+            self.chain[position].sign(&self.signing_key);
+        }
+        self.integrity = self.verify();
+
+        unsigned.iter().map(|&position| &self.chain[position]).collect()
+    }
+
+    // Valida a eleição e monta o bloco que a registra, sem alterar a cadeia
+    pub fn create_election(&self, election: NewElection, creator_id: String) -> Result<Block, AppError> {
+        election.validate().map_err(AppError::InvalidElection)?;
+
+        // O slug não pode repetir outro slug nem o id de uma eleição antiga,
+        // criada quando o id ainda era escolhido pelo cliente
+        if let Some(slug) = &election.slug {
+            if self.resolve_election_id(slug).is_some() {
+                return Err(AppError::SlugTaken);
             }
-        } else {
-            return Err("Election does not exist".to_string());
+        }
+
+        // Ids aleatórios: não dá para adivinhar nem reservar o id de outra eleição
+        let election_id = loop {
+            let election_id = Uuid::new_v4().to_string();
+            if !self.elections.contains_key(&election_id) {
+                break election_id;
+            }
+        };
+
+        let NewElection { slug, title, description, options, starts_at, ends_at, voting_method, max_selections, eligibility, secret_ballot } = election;
+
+        if let Some(max_selections) = max_selections {
+            if voting_method != VotingMethod::Approval {
+                return Err(AppError::InvalidElection("max_selections is only allowed in approval elections".to_string()));
+            }
+            if max_selections == 0 || max_selections as usize > options.len() {
+                return Err(AppError::InvalidElection("max_selections must be between 1 and the number of vote options".to_string()));
+            }
+        }
+
+        if let Some(ends_at) = ends_at {
+            if u128::from(ends_at) <= current_timestamp() {
+                return Err(AppError::InvalidElection("Election end time must be in the future".to_string()));
+            }
+            if starts_at.is_some_and(|starts_at| starts_at >= ends_at) {
+                return Err(AppError::InvalidElection("Election must start before it ends".to_string()));
+            }
+        }
+
+        // A ordem das opções é a do criador e fica gravada no bloco
+        let vote_options = options.iter().map(|option| option.id.clone()).collect();
+
+        Ok(self.next_block(BlockPayload::ElectionCreated {
+            election_id,
+            creator_id,
+            slug,
+            vote_options,
+            title,
+            description,
+            options,
+            starts_at,
+            ends_at,
+            voting_method,
+            max_selections,
+            eligibility,
+            secret_ballot,
+        }))
+    }
+
+    // Id da eleição a partir do próprio id ou do slug
+    pub fn resolve_election_id(&self, id_or_slug: &str) -> Option<String> {
+        if self.elections.contains_key(id_or_slug) {
+            return Some(id_or_slug.to_string());
+        }
+        self.slugs.get(id_or_slug).cloned()
+    }
+
+    pub fn is_creator(&self, user_id: &str, election_id: &str) -> bool {
+        self.creators
+            .get(user_id)
+            .is_some_and(|elections| elections.iter().any(|created| created == election_id))
+    }
+
+    pub fn election_status(&self, election_id: &str) -> Option<ElectionStatus> {
+        self.schedules
+            .get(election_id)
+            .map(|schedule| schedule.status(current_timestamp()))
+    }
+
+    // Encerra a eleição e publica a raiz de Merkle das cédulas; só o criador
+    // pode encerrar. Uma eleição que terminou pelo horário ainda pode receber
+    // o registro de encerramento, para que a raiz fique publicada na cadeia.
+    pub fn close_election(&self, election_id: String, requester_id: String) -> Result<Block, AppError> {
+        let status = match self.election_status(&election_id) {
+            Some(status) => status,
+            None => return Err(AppError::ElectionNotFound),
+        };
+
+        if !self.is_creator(&requester_id, &election_id) {
+            return Err(AppError::NotElectionCreator);
+        }
+
+        let has_close_record = self.schedules
+            .get(&election_id)
+            .is_some_and(|schedule| schedule.closed_at.is_some());
+        if status == ElectionStatus::Closed && has_close_record {
+            return Err(AppError::ElectionAlreadyClosed);
+        }
+
+        Ok(self.close_block(election_id, requester_id))
+    }
+
+    // Eleições que terminaram pelo horário e ainda não têm registro de
+    // encerramento, e portanto nem raiz de Merkle publicada
+    pub fn ended_without_close_record(&self) -> Vec<String> {
+        let now = current_timestamp();
+        let mut ended: Vec<String> = self.schedules
+            .iter()
+            .filter(|(_, schedule)| schedule.closed_at.is_none() && schedule.status(now) == ElectionStatus::Closed)
+            .map(|(election_id, _)| election_id.clone())
+            .collect();
+        ended.sort_by_key(|election_id| self.index.position(election_id));
+        ended
+    }
+
+    // Monta o registro de encerramento de uma eleição que terminou pelo
+    // horário, em nome do servidor. None se ela não precisar mais dele.
+    pub fn close_ended_election(&self, election_id: &str) -> Option<Block> {
+        let schedule = self.schedules.get(election_id)?;
+        if schedule.closed_at.is_some() || schedule.status(current_timestamp()) != ElectionStatus::Closed {
+            return None;
+        }
+        Some(self.close_block(election_id.to_string(), SCHEDULED_CLOSER.to_string()))
+    }
+
+    fn close_block(&self, election_id: String, closed_by: String) -> Block {
+        let ballot_count = self.merkle_trees.get(&election_id).map_or(0, MerkleTree::len);
+
+        self.next_block(BlockPayload::ElectionClosed {
+            ballots_root: Some(self.ballots_root(&election_id)),
+            ballot_count: Some(ballot_count as u64),
+            election_id,
+            closed_by,
+        })
+    }
+
+    // Inclui ou retira eleitores da lista de aptos. Só o criador pode mudar a
+    // lista, e só enquanto a eleição ainda não abriu.
+    pub fn change_eligibility(&self, election_id: String, requester_id: String, added: Vec<String>, removed: Vec<String>) -> Result<Block, AppError> {
+        let status = match self.election_status(&election_id) {
+            Some(status) => status,
+            None => return Err(AppError::ElectionNotFound),
+        };
+
+        if !self.is_creator(&requester_id, &election_id) {
+            return Err(AppError::NotElectionCreator);
+        }
+
+        if status != ElectionStatus::Scheduled {
+            return Err(AppError::InvalidElection("Eligible voters can only be changed before the election opens".to_string()));
+        }
+
+        let normalize = |emails: Vec<String>| {
+            emails.iter()
+                .map(|email| normalize_email(email))
+                .collect::<Result<Vec<String>, String>>()
+                .map_err(AppError::InvalidPayload)
+        };
+        let mut added = normalize(added)?;
+        let mut removed = normalize(removed)?;
+        added.sort();
+        added.dedup();
+        removed.sort();
+        removed.dedup();
+
+        if added.is_empty() && removed.is_empty() {
+            return Err(AppError::MissingParameter("voters"));
+        }
+
+        // Esvaziar a lista abriria a eleição para todos; isso não pode acontecer por acidente
+        let mut eligibility = self.eligibility.get(&election_id).cloned().unwrap_or_default();
+        let was_restricted = !eligibility.is_open();
+        eligibility.voters.extend(added.iter().cloned());
+        for voter in &removed {
+            eligibility.voters.remove(voter);
+        }
+        if was_restricted && eligibility.is_open() {
+            return Err(AppError::InvalidElection("Eligibility list cannot be left empty".to_string()));
+        }
+
+        Ok(self.next_block(BlockPayload::EligibilityChanged {
+            election_id,
+            changed_by: requester_id,
+            added,
+            removed,
+        }))
+    }
+
+    // Valida o voto e monta o próximo bloco, sem alterar a cadeia.
+    // O bloco só entra na cadeia via `commit_block`, depois de persistido.
+    // `ballot` traz as opções escolhidas: uma só na maioria simples, a ordem
+    // de preferência numa eleição ranqueada ou as opções aprovadas.
+    // Em eleições com voto secreto também devolve o segredo da cédula, que
+    // precisa ser entregue ao eleitor e nunca é guardado pelo servidor.
+    pub fn add_vote_operation(&self, voter_id: String, election_id: String, mut ballot: Vec<String>) -> Result<(Block, Option<String>), AppError> {
+        let election = match self.elections.get(&election_id) {
+            Some(election) => election,
+            None => return Err(AppError::ElectionNotFound),
+        };
+
+        let eligible = self.eligibility
+            .get(&election_id)
+            .is_none_or(|eligibility| eligibility.allows(&voter_id));
+        if !eligible {
+            return Err(AppError::NotEligible);
+        }
+
+        if ballot.iter().any(|option| !election.has_option(option)) {
+            return Err(AppError::OptionInvalid);
+        }
+
+        let voting_method = self.methods.get(&election_id).copied().unwrap_or_default();
+        match voting_method {
+            VotingMethod::Plurality if ballot.len() != 1 => {
+                return Err(AppError::InvalidBallot("This election accepts exactly one vote option".to_string()));
+            }
+            VotingMethod::RankedChoice if ballot.is_empty() => {
+                return Err(AppError::InvalidBallot("Ballot must rank at least one vote option".to_string()));
+            }
+            VotingMethod::Approval if ballot.is_empty() => {
+                return Err(AppError::InvalidBallot("Ballot must approve at least one vote option".to_string()));
+            }
+            VotingMethod::Approval => {
+                if let Some(max_selections) = self.max_selections.get(&election_id) {
+                    if ballot.len() > *max_selections as usize {
+                        return Err(AppError::InvalidBallot(format!("This election accepts at most {} vote options", max_selections)));
+                    }
+                }
+                // Na aprovação a ordem não importa; ordenar evita que ela identifique a cédula
+                ballot.sort();
+            }
+            _ => {}
+        }
+
+        let distinct: HashSet<&String> = ballot.iter().collect();
+        if distinct.len() != ballot.len() {
+            return Err(AppError::InvalidBallot("Ballot contains the same vote option more than once".to_string()));
+        }
+
+        match self.election_status(&election_id) {
+            Some(ElectionStatus::Scheduled) => return Err(AppError::ElectionNotStarted),
+            Some(ElectionStatus::Closed) => return Err(AppError::ElectionClosed),
+            _ => {}
         }
 
         if let Some(voters) = self.voters.get(&election_id) {
             if voters.contains(&voter_id) {
-                return Err("Voter has already voted in this election".to_string());
+                return Err(AppError::AlreadyVoted);
             }
-        } else {
-            self.voters.insert(election_id.clone(), HashSet::new());
         }
 
-        let last_block = self.chain.last().unwrap();
-        let new_block = Block::new(
-            last_block.index + 1,
-            voter_id.clone(),
-            election_id.clone(),
+        // A primeira opção da cédula continua em `vote_option_id`, para que
+        // consultas do voto do usuário funcionem para qualquer forma de votação
+        let vote_option_id = ballot[0].clone();
+        let vote_option_ids = if voting_method.is_plurality() { Vec::new() } else { ballot };
+
+        if self.secret_ballots.contains(&election_id) {
+            let ballot_secret = generate_ballot_secret();
+            let block = self.next_block(BlockPayload::BallotCast {
+                election_id,
+                ballot_id: ballot_id(&ballot_secret),
+                vote_option_id,
+                vote_option_ids,
+            });
+            return Ok((block, Some(ballot_secret)));
+        }
+
+        let block = self.next_block(BlockPayload::VoteCast {
+            voter_id,
+            election_id,
             vote_option_id,
-            last_block.hash.clone(),
-        );
+            vote_option_ids,
+        });
+        Ok((block, None))
+    }
 
-        self.chain.push(new_block);
-        self.voters.get_mut(&election_id).unwrap().insert(voter_id);
+    // Refaz os mapas derivados reaplicando a cadeia, depois de uma atualização
+    // interrompida no meio (ver blockchain::ledger). A participação nas
+    // eleições secretas não está na cadeia e é preservada.
+    pub fn rebuild(&mut self) {
+        let chain = std::mem::take(&mut self.chain);
+        let voters = std::mem::take(&mut self.voters);
 
-        Ok(())
+        let mut rebuilt = Blockchain::replay(chain, self.signing_key.clone());
+        for (election_id, voters) in voters {
+            rebuilt.voters.entry(election_id).or_default().extend(voters);
+        }
+        *self = rebuilt;
+    }
+
+    // Registra que o eleitor já votou numa eleição com voto secreto, onde a
+    // cédula na cadeia não diz quem votou
+    pub fn record_participation(&mut self, election_id: String, voter_id: String) {
+        self.voters
+            .entry(election_id)
+            .or_default()
+            .insert(voter_id);
+    }
+
+    // Anexa o bloco à cadeia e atualiza os mapas derivados dele
+    pub fn commit_block(&mut self, block: Block) {
+        let position = self.chain.len();
+
+        // Depois do primeiro bloco quebrado a cadeia já não é válida
+        if self.integrity.is_ok() {
+            self.integrity = self.check_next(&block);
+        }
+
+        match &block.payload {
+            BlockPayload::Genesis => {}
+            BlockPayload::ElectionCreated { election_id, creator_id, slug, vote_options, title, description, options, starts_at, ends_at, voting_method, max_selections, eligibility, secret_ballot } => {
+                self.creators
+                    .entry(creator_id.clone())
+                    .or_default()
+                    .push(election_id.clone());
+                // Eleições antigas só têm os ids; o rótulo passa a ser o próprio id
+                let options = if options.is_empty() {
+                    vote_options.iter().cloned().map(VoteOption::from_id).collect()
+                } else {
+                    options.clone()
+                };
+                if let Some(slug) = slug {
+                    self.slugs.insert(slug.clone(), election_id.clone());
+                }
+                self.index.insert(election_id, block.timestamp, title.as_deref(), slug.as_deref());
+                self.tallies.insert(election_id.clone(), RunningTally::new(vote_options));
+                self.elections.insert(election_id.clone(), Election {
+                    election_id: election_id.clone(),
+                    slug: slug.clone(),
+                    created_at: block.timestamp,
+                    title: title.clone(),
+                    description: description.clone(),
+                    options,
+                });
+                self.schedules.insert(election_id.clone(), ElectionSchedule {
+                    starts_at: starts_at.map(u128::from),
+                    ends_at: ends_at.map(u128::from),
+                    closed_at: None,
+                });
+                self.methods.insert(election_id.clone(), *voting_method);
+                if let Some(max_selections) = max_selections {
+                    self.max_selections.insert(election_id.clone(), *max_selections);
+                }
+                self.eligibility.insert(election_id.clone(), eligibility.clone());
+                if *secret_ballot {
+                    self.secret_ballots.insert(election_id.clone());
+                }
+            }
+            // A participação vem da tabela `election_participation`, não da cadeia
+            BlockPayload::BallotCast { ballot_id, .. } => {
+                self.ballots_by_id.insert(ballot_id.clone(), position);
+            }
+            BlockPayload::EligibilityChanged { election_id, added, removed, .. } => {
+                let eligibility = self.eligibility.entry(election_id.clone()).or_default();
+                eligibility.voters.extend(added.iter().cloned());
+                for voter in removed {
+                    eligibility.voters.remove(voter);
+                }
+            }
+            BlockPayload::ElectionClosed { election_id, ballots_root, .. } => {
+                if let Some(schedule) = self.schedules.get_mut(election_id) {
+                    // Se a eleição já tinha acabado pelo horário, vale o horário previsto
+                    let ended_at = schedule.ends_at.filter(|ends_at| *ends_at <= block.timestamp);
+                    schedule.closed_at = Some(ended_at.unwrap_or(block.timestamp));
+                }
+                if let Some(root) = ballots_root {
+                    self.merkle_roots.insert(election_id.clone(), root.clone());
+                }
+            }
+            BlockPayload::VoteCast { voter_id, election_id, .. } => {
+                self.voters
+                    .entry(election_id.clone())
+                    .or_default()
+                    .insert(voter_id.clone());
+                self.votes_by_voter
+                    .entry(voter_id.clone())
+                    .or_default()
+                    .insert(election_id.clone(), position);
+            }
+        }
+
+        if let Some(election_id) = block.payload.election_id() {
+            self.election_blocks
+                .entry(election_id.clone())
+                .or_default()
+                .push(position);
+            if let Some(ballot) = block.payload.ballot() {
+                self.tallies
+                    .entry(election_id.clone())
+                    .or_default()
+                    .record(&ballot);
+                let leaf_index = self.merkle_trees
+                    .entry(election_id.clone())
+                    .or_default()
+                    .push(&ballot_commitment(&block.payload));
+                self.leaf_indexes.insert(position, leaf_index);
+            }
+        }
+        self.block_positions.insert(block.hash.clone(), position);
+
+        self.chain.push(block);
     }
 
+    // Blocos de uma eleição, na ordem da cadeia
+    fn election_blocks(&self, election_id: &str) -> impl Iterator<Item = &Block> {
+        self.election_blocks
+            .get(election_id)
+            .into_iter()
+            .flatten()
+            .map(|position| &self.chain[*position])
+    }
+
+    // Cédulas de uma eleição (votos abertos e secretos), na ordem da cadeia
+    fn election_ballots(&self, election_id: &str) -> impl Iterator<Item = &Block> {
+        self.election_blocks(election_id)
+            .filter(|block| matches!(block.payload, BlockPayload::VoteCast { .. } | BlockPayload::BallotCast { .. }))
+    }
+
+    fn block_by_hash(&self, block_hash: &str) -> Option<(usize, &Block)> {
+        let position = *self.block_positions.get(block_hash)?;
+        Some((position, &self.chain[position]))
+    }
+
+    // Voto registrado na posição, como (election_id, vote_option_id)
+    fn vote_at(&self, position: usize) -> Option<(String, String)> {
+        match &self.chain.get(position)?.payload {
+            BlockPayload::VoteCast { election_id, vote_option_id, .. }
+            | BlockPayload::BallotCast { election_id, vote_option_id, .. } => Some((election_id.clone(), vote_option_id.clone())),
+            _ => None,
+        }
+    }
+
+    // Só encontra votos abertos; numa eleição secreta o voto só é achado pelo segredo da cédula
     pub fn get_votes_by_user(&self, voter_id: &str, election_id: &str) -> Option<(String, String)> {
-        self.chain
-            .iter()
-            .rev()  // Itera reversamente para pegar o voto mais recente primeiro
-            .find(|block| block.voter_id == voter_id && block.election_id == election_id)
-            .map(|block| (block.election_id.clone(), block.vote_option_id.clone()))
+        let position = self.votes_by_voter.get(voter_id)?.get(election_id)?;
+        self.vote_at(*position)
     }
 
+    // Votos abertos do eleitor, na ordem em que foram registrados
     pub fn get_elections_by_user(&self, voter_id: &str) -> Vec<(String, String)> {
-        self.chain
-            .iter()
-            .filter(|block| block.voter_id == voter_id)
-            .map(|block| (block.election_id.clone(), block.vote_option_id.clone()))
-            .collect()
-    
-    
+        let mut positions: Vec<usize> = self.votes_by_voter
+            .get(voter_id)
+            .map_or_else(Vec::new, |votes| votes.values().copied().collect());
+        positions.sort_unstable();
+        positions.into_iter().filter_map(|position| self.vote_at(position)).collect()
+    }
+
+    // Contagem parcial da eleição, atualizada a cada voto
+    pub fn running_tally(&self, election_id: &str) -> Option<&RunningTally> {
+        self.tallies.get(election_id)
+    }
+
+    // Confere o comprovante de voto do bloco `block_hash`. Retorna None se o
+    // bloco não existir ou não for uma cédula.
+    pub fn verify_receipt(&self, block_hash: &str, commitment: Option<&str>) -> Option<ReceiptVerification> {
+        let (position, block) = self.block_by_hash(block_hash)?;
+
+        let election_id = match &block.payload {
+            BlockPayload::VoteCast { election_id, .. } | BlockPayload::BallotCast { election_id, .. } => election_id.clone(),
+            _ => return None,
+        };
+
+        let hash_valid = calculate_hash(block.index, block.timestamp, &block.payload, &block.previous_hash) == block.hash;
+        let linked = position > 0 && self.chain[position - 1].hash == block.previous_hash;
+        let included = hash_valid && linked;
+
+        let recorded_commitment = ballot_commitment(&block.payload);
+        let commitment_matches = commitment.map(|commitment| commitment == recorded_commitment);
+
+        let election_status = self.election_status(&election_id);
+
+        Some(ReceiptVerification {
+            block_index: block.index,
+            block_hash: block.hash.clone(),
+            election_id,
+            ballot_commitment: recorded_commitment,
+            included,
+            commitment_matches,
+            counted: included && self.integrity.is_ok(),
+            final_tally: election_status == Some(ElectionStatus::Closed),
+            election_status,
+        })
+    }
+
+    // Raiz atual da árvore de Merkle das cédulas da eleição
+    fn ballots_root(&self, election_id: &str) -> String {
+        self.merkle_trees
+            .get(election_id)
+            .map_or_else(|| merkle_root(&[]), MerkleTree::root)
+    }
+
+    // Raiz publicada no encerramento ou, se ainda não houver, a raiz atual
+    // (com flag indicando se é a publicada) e o número de cédulas
+    pub fn election_merkle_root(&self, election_id: &str) -> (String, bool, usize) {
+        let ballot_count = self.merkle_trees.get(election_id).map_or(0, MerkleTree::len);
+        match self.merkle_roots.get(election_id) {
+            Some(root) => (root.clone(), true, ballot_count),
+            None => (self.ballots_root(election_id), false, ballot_count),
+        }
+    }
+
+    pub fn ballot_proof(&self, block_hash: &str) -> Option<BallotProof> {
+        let (position, block) = self.block_by_hash(block_hash)?;
+        let election_id = match &block.payload {
+            BlockPayload::VoteCast { election_id, .. } | BlockPayload::BallotCast { election_id, .. } => election_id.clone(),
+            _ => return None,
+        };
+
+        let leaf_index = *self.leaf_indexes.get(&position)?;
+        let proof = self.merkle_trees.get(&election_id)?.proof(leaf_index)?;
+        let (root, published, ballot_count) = self.election_merkle_root(&election_id);
+
+        Some(BallotProof {
+            election_id,
+            block_hash: block.hash.clone(),
+            ballot_commitment: ballot_commitment(&block.payload),
+            leaf_index,
+            ballot_count,
+            proof,
+            root,
+            published,
+        })
+    }
+
+    // Cédula secreta correspondente ao segredo guardado pelo eleitor, como (election_id, vote_option_id)
+    pub fn get_ballot_by_secret(&self, ballot_secret: &str) -> Option<(String, String)> {
+        let position = self.ballots_by_id.get(&ballot_id(ballot_secret))?;
+        self.vote_at(*position)
+    }
+
+    // Uma página da busca de eleições, na ordem pedida, começando depois do
+    // cursor (a posição da última eleição da página anterior). Devolve os ids
+    // e o cursor da próxima página, se houver.
+    pub fn browse_elections(&self, filter: &ElectionFilter, sort: ElectionSort, cursor: Option<usize>, limit: usize) -> (Vec<String>, Option<usize>) {
+        let created = self.index.created_between(filter.created_after, filter.created_before);
+
+        // Busca por texto e criador reduzem os candidatos antes de ordenar
+        let mut candidates: Option<BTreeSet<usize>> = filter.query
+            .as_deref()
+            .map(|query| self.index.search(query));
+        if let Some(creator) = &filter.creator {
+            let created_by: BTreeSet<usize> = self.creators
+                .get(creator)
+                .into_iter()
+                .flatten()
+                .filter_map(|election_id| self.index.position(election_id))
+                .collect();
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&created_by).copied().collect(),
+                None => created_by,
+            });
+        }
+
+        let newest_end = cursor.map_or(created.end, |cursor| cursor.min(created.end)).max(created.start);
+        let oldest_start = cursor.map_or(created.start, |cursor| cursor.saturating_add(1).max(created.start)).min(created.end);
+
+        let positions: Box<dyn Iterator<Item = usize> + '_> = match (sort, &candidates) {
+            (ElectionSort::Newest, None) => Box::new((created.start..newest_end).rev()),
+            (ElectionSort::Oldest, None) => Box::new(oldest_start..created.end),
+            (ElectionSort::Title, None) => Box::new(self.index.by_title_after(cursor).filter(|position| created.contains(position))),
+            (ElectionSort::Newest, Some(candidates)) => Box::new(candidates.range(created.start..newest_end).rev().copied()),
+            (ElectionSort::Oldest, Some(candidates)) => Box::new(candidates.range(oldest_start..created.end).copied()),
+            (ElectionSort::Title, Some(candidates)) => {
+                let after = cursor.and_then(|cursor| self.index.title_key(cursor).map(|key| (key, cursor)));
+                let mut sorted: Vec<(&String, usize)> = candidates
+                    .range(created.clone())
+                    .filter_map(|position| self.index.title_key(*position).map(|key| (key, *position)))
+                    .filter(|key| after.is_none_or(|after| *key > after))
+                    .collect();
+                sorted.sort();
+                Box::new(sorted.into_iter().map(|(_, position)| position))
+            }
+        };
+
+        // O status não tem índice porque depende do relógio: uma eleição abre e
+        // termina pelo horário sem que nenhum bloco seja anexado, e um índice
+        // montado em `commit_block` ficaria velho. O filtro percorre os
+        // candidatos já ordenados e para no `limit + 1`-ésimo que bate; só fica
+        // caro quando quase nenhuma eleição tem o status pedido, e aí vale
+        // combinar com `creator`, `q` ou as datas, que são indexados.
+        let mut page: Vec<usize> = positions
+            .filter(|position| {
+                let election_id = self.index.election_id(*position);
+                filter.status.is_none_or(|status| election_id.and_then(|id| self.election_status(id)) == Some(status))
+            })
+            .take(limit + 1)
+            .collect();
+
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().copied()
+        } else {
+            None
+        };
+
+        let election_ids = page
+            .into_iter()
+            .filter_map(|position| self.index.election_id(position).cloned())
+            .collect();
+        (election_ids, next_cursor)
     }
 
     pub fn get_elections_created_by_user(&self, creator_id: &str) -> Vec<String> {
         self.creators
         .get(creator_id)
-        .map_or_else(Vec::new, |elections| elections.iter().cloned().collect())
-    
-    
+        .map_or_else(Vec::new, |elections| elections.clone())
+
+
     }
 
-    pub fn get_results_election(&self, election_id: &str) -> Vec<(String, String)> {
-        self.chain
-            .iter()
-            .filter(|block| block.election_id == election_id)
-            .map(|block| (block.election_id.clone(), block.vote_option_id.clone()))
+    // Cédulas completas de uma eleição, na ordem em que foram registradas
+    pub fn get_ballots_election(&self, election_id: &str) -> Vec<Vec<String>> {
+        self.election_ballots(election_id)
+            .filter_map(|block| block.payload.ballot())
             .collect()
-    
     }
 
+    // Pacote de auditoria da eleição (ver blockchain::audit), já assinado
+    pub fn export_bundle(&self, election_id: &str) -> Option<String> {
+        let blocks: Vec<&Block> = self.election_blocks(election_id).collect();
+
+        let definition = blocks.iter().find_map(|block| ElectionDefinition::from_payload(&block.payload))?;
+        let ballots = self.get_ballots_election(election_id);
+        let tally = tally(definition.voting_method, &definition.vote_options, &ballots);
+        let (root, published, ballot_count) = self.election_merkle_root(election_id);
+
+        let mut lines = vec![
+            BundleLine::Header(BundleHeader {
+                format: BUNDLE_FORMAT.to_string(),
+                version: BUNDLE_VERSION,
+                election_id: election_id.to_string(),
+                exported_at: current_timestamp(),
+                public_key: self.verifying_key_hex(),
+            }),
+            BundleLine::Election(definition),
+        ];
+        lines.extend(blocks.into_iter().map(|block| BundleLine::Block(block.clone())));
+        lines.push(BundleLine::Tally(tally));
+        lines.push(BundleLine::MerkleRoot(BundleMerkleRoot { root, published, ballot_count }));
+
+        let mut bundle = String::new();
+        for line in &lines {
+            bundle.push_str(&serde_json::to_string(line).ok()?);
+            bundle.push('\n');
+        }
+
+        let signature = self.signing_key.sign(bundle.as_bytes());
+        bundle.push_str(&serde_json::to_string(&BundleLine::Signature(hex::encode(signature.to_bytes()))).ok()?);
+        bundle.push('\n');
+
+        Some(bundle)
+    }
+
+}
+
+// Verificação completa de uma cópia da cadeia, como `Blockchain::verify`, para
+// ser feita sem segurar o ledger enquanto as assinaturas são conferidas
+pub fn verify_blocks(chain: &[Block], verifying_key: &VerifyingKey) -> Result<(), ChainError> {
+    check_chain(chain, verifying_key, true)
+}
+
+fn check_chain(chain: &[Block], verifying_key: &VerifyingKey, require_signatures: bool) -> Result<(), ChainError> {
+    if chain.is_empty() {
+        return Err(ChainError { index: 0, reason: ChainErrorReason::MissingGenesis });
+    }
+
+    // Commitments das cédulas vistas até aqui, por eleição, para conferir as raízes publicadas
+    let mut commitments: HashMap<&String, Vec<String>> = HashMap::new();
+
+    for (position, block) in chain.iter().enumerate() {
+        let previous = position.checked_sub(1).map(|previous| &chain[previous]);
+        check_block(previous, position, block, verifying_key, require_signatures)?;
+
+        match &block.payload {
+            BlockPayload::VoteCast { election_id, .. } | BlockPayload::BallotCast { election_id, .. } => {
+                commitments.entry(election_id).or_default().push(ballot_commitment(&block.payload));
+            }
+            BlockPayload::ElectionClosed { election_id, ballots_root: Some(root), .. } => {
+                let ballots = commitments.get(election_id).map(Vec::as_slice).unwrap_or_default();
+                check_root(block, root, &merkle_root(ballots))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Confere o bloco na posição `position` contra o anterior (ou as regras do
+// gênesis, se for o primeiro): índice, hash, assinatura e encadeamento
+fn check_block(previous: Option<&Block>, position: usize, block: &Block, verifying_key: &VerifyingKey, require_signatures: bool) -> Result<(), ChainError> {
+    if previous.is_none() && (block.index != 0 || block.previous_hash != "0" || block.payload != BlockPayload::Genesis) {
+        return Err(ChainError { index: block.index, reason: ChainErrorReason::MissingGenesis });
+    }
+
+    if block.index != position as u64 {
+        return Err(ChainError { index: block.index, reason: ChainErrorReason::NonMonotonicIndex });
+    }
+
+    let hash = calculate_hash(block.index, block.timestamp, &block.payload, &block.previous_hash);
+    if hash != block.hash {
+        return Err(ChainError { index: block.index, reason: ChainErrorReason::InvalidHash });
+    }
+
+    match &block.signature {
+        None if require_signatures => {
+            return Err(ChainError { index: block.index, reason: ChainErrorReason::MissingSignature });
+        }
+        Some(_) if !block.has_valid_signature(verifying_key) => {
+            return Err(ChainError { index: block.index, reason: ChainErrorReason::InvalidSignature });
+        }
+        _ => {}
+    }
+
+    if let Some(previous) = previous {
+        if block.previous_hash != previous.hash {
+            return Err(ChainError { index: block.index, reason: ChainErrorReason::BrokenLink });
+        }
+        if block.timestamp < previous.timestamp {
+            return Err(ChainError { index: block.index, reason: ChainErrorReason::NonMonotonicTimestamp });
+        }
+    }
+
+    Ok(())
+}
+
+// Confere a raiz publicada no encerramento contra a calculada das cédulas
+fn check_root(block: &Block, root: &str, expected: &str) -> Result<(), ChainError> {
+    if root != expected {
+        return Err(ChainError { index: block.index, reason: ChainErrorReason::MerkleRootMismatch });
+    }
+    Ok(())
 }
 
-pub type SharedBlockchain = Arc<Mutex<Blockchain>>;
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TITLES: [&str; 9] = ["Mascote", "Orçamento 2025", "Mascote", "Diretoria", "mascote do time", "Orçamento 2026", "Zebra", "Diretoria", "Assembleia"];
+
+    fn new_election(title: &str, starts_at: Option<u64>) -> NewElection {
+        NewElection {
+            slug: None,
+            title: Some(title.to_string()),
+            description: None,
+            options: vec![VoteOption::from_id("a".to_string()), VoteOption::from_id("b".to_string())],
+            starts_at,
+            ends_at: None,
+            voting_method: VotingMethod::Plurality,
+            max_selections: None,
+            eligibility: Eligibility::default(),
+            secret_ballot: false,
+        }
+    }
+
+    // Eleições de dois criadores, com títulos repetidos (o desempate é pela
+    // posição) e os três status: agendada, aberta e encerrada
+    fn sample_blockchain() -> Blockchain {
+        let mut blockchain = Blockchain::new(SigningKey::from_bytes(&[7; 32]));
+        let tomorrow = (current_timestamp() + 24 * 60 * 60 * 1000) as u64;
+
+        for (i, title) in TITLES.iter().enumerate() {
+            let creator = if i % 2 == 0 { "a@x.com" } else { "b@x.com" };
+            let starts_at = (i % 3 == 1).then_some(tomorrow);
+
+            let block = blockchain.create_election(new_election(title, starts_at), creator.to_string()).unwrap();
+            let election_id = block.payload.election_id().unwrap().clone();
+            blockchain.commit_block(block);
+
+            if i % 3 == 2 {
+                let block = blockchain.close_election(election_id, creator.to_string()).unwrap();
+                blockchain.commit_block(block);
+            }
+        }
+        blockchain
+    }
+
+    // Segue o `next_cursor` até o fim
+    fn browse_all(blockchain: &Blockchain, filter: &ElectionFilter, sort: ElectionSort, limit: usize) -> Vec<String> {
+        let mut found = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next_cursor) = blockchain.browse_elections(filter, sort, cursor, limit);
+            assert!(page.len() <= limit);
+            found.extend(page);
+            assert!(found.len() <= TITLES.len(), "paging does not stop");
+
+            match next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => return found,
+            }
+        }
+    }
+
+    // O resultado esperado, calculado olhando cada eleição
+    fn expected(blockchain: &Blockchain, filter: &ElectionFilter, sort: ElectionSort) -> Vec<String> {
+        let matches_query = filter.query.as_deref().map(|query| blockchain.index.search(query));
+        let mut positions: Vec<usize> = (0..TITLES.len())
+            .filter(|position| {
+                let election_id = blockchain.index.election_id(*position).unwrap();
+                let created_at = blockchain.elections[election_id].created_at;
+                filter.status.is_none_or(|status| blockchain.election_status(election_id) == Some(status))
+                    && filter.creator.as_ref().is_none_or(|creator| blockchain.creators[creator].contains(election_id))
+                    && filter.created_after.is_none_or(|after| created_at >= after)
+                    && filter.created_before.is_none_or(|before| created_at <= before)
+                    && matches_query.as_ref().is_none_or(|matches| matches.contains(position))
+            })
+            .collect();
+
+        match sort {
+            ElectionSort::Newest => positions.reverse(),
+            ElectionSort::Oldest => {}
+            ElectionSort::Title => positions.sort_by_key(|position| (blockchain.index.title_key(*position).unwrap().clone(), *position)),
+        }
+        positions.iter().map(|position| blockchain.index.election_id(*position).unwrap().clone()).collect()
+    }
+
+    fn filters(blockchain: &Blockchain) -> Vec<ElectionFilter> {
+        let third_created_at = blockchain.elections[blockchain.index.election_id(2).unwrap()].created_at;
+        vec![
+            ElectionFilter::default(),
+            ElectionFilter { status: Some(ElectionStatus::Open), ..Default::default() },
+            ElectionFilter { status: Some(ElectionStatus::Scheduled), ..Default::default() },
+            ElectionFilter { status: Some(ElectionStatus::Closed), ..Default::default() },
+            ElectionFilter { creator: Some("a@x.com".to_string()), ..Default::default() },
+            ElectionFilter { query: Some("mascote".to_string()), ..Default::default() },
+            ElectionFilter { query: Some("orç".to_string()), status: Some(ElectionStatus::Scheduled), ..Default::default() },
+            ElectionFilter { created_after: Some(third_created_at), ..Default::default() },
+            ElectionFilter { created_before: Some(third_created_at), creator: Some("b@x.com".to_string()), ..Default::default() },
+        ]
+    }
+
+    // Recalcula o hash e assina de novo, como faria quem tem a chave do servidor
+    fn reseal(block: &mut Block, signing_key: &SigningKey) {
+        block.hash = calculate_hash(block.index, block.timestamp, &block.payload, &block.previous_hash);
+        block.sign(signing_key);
+    }
+
+    fn failure(blockchain: &Blockchain) -> (u64, ChainErrorReason) {
+        let err = blockchain.verify().unwrap_err();
+        (err.index, err.reason)
+    }
+
+    #[test]
+    fn untouched_chain_verifies_and_loads() {
+        let blockchain = sample_blockchain();
+
+        assert!(blockchain.verify().is_ok());
+        assert!(blockchain.integrity().is_ok());
+        assert!(Blockchain::from_stored(blockchain.chain.clone(), blockchain.signing_key.clone()).is_ok());
+    }
+
+    #[test]
+    fn edited_payload_is_an_invalid_hash() {
+        let mut blockchain = sample_blockchain();
+        blockchain.chain[2].payload = blockchain.chain[1].payload.clone();
+
+        assert_eq!(failure(&blockchain), (2, ChainErrorReason::InvalidHash));
+    }
+
+    #[test]
+    fn wrong_previous_hash_is_a_broken_link() {
+        let mut blockchain = sample_blockchain();
+        let signing_key = blockchain.signing_key.clone();
+        blockchain.chain[3].previous_hash = blockchain.chain[1].hash.clone();
+        reseal(&mut blockchain.chain[3], &signing_key);
+
+        assert_eq!(failure(&blockchain), (3, ChainErrorReason::BrokenLink));
+    }
+
+    #[test]
+    fn out_of_order_index_or_timestamp_is_rejected() {
+        let mut blockchain = sample_blockchain();
+        let signing_key = blockchain.signing_key.clone();
+        blockchain.chain[2].index = 5;
+        reseal(&mut blockchain.chain[2], &signing_key);
+
+        assert_eq!(failure(&blockchain), (5, ChainErrorReason::NonMonotonicIndex));
+
+        let mut blockchain = sample_blockchain();
+        blockchain.chain[2].timestamp = blockchain.chain[1].timestamp - 1;
+        reseal(&mut blockchain.chain[2], &signing_key);
+
+        assert_eq!(failure(&blockchain), (2, ChainErrorReason::NonMonotonicTimestamp));
+    }
+
+    #[test]
+    fn tampered_chain_is_refused_at_startup() {
+        let blockchain = sample_blockchain();
+        let mut blocks = blockchain.chain.clone();
+        blocks[4].payload = blocks[1].payload.clone();
+
+        let err = Blockchain::from_stored(blocks, blockchain.signing_key.clone()).unwrap_err();
+        assert!(err.contains("block 4"), "{}", err);
+    }
+
+    #[test]
+    fn rehashed_block_without_a_new_signature_is_rejected() {
+        let mut blockchain = sample_blockchain();
+        let block = &mut blockchain.chain[2];
+        block.payload = BlockPayload::Genesis;
+        block.hash = calculate_hash(block.index, block.timestamp, &block.payload, &block.previous_hash);
+
+        assert_eq!(failure(&blockchain), (2, ChainErrorReason::InvalidSignature));
+    }
+
+    #[test]
+    fn block_signed_by_another_key_is_rejected() {
+        let mut blockchain = sample_blockchain();
+        blockchain.chain[3].sign(&SigningKey::from_bytes(&[8; 32]));
+
+        assert_eq!(failure(&blockchain), (3, ChainErrorReason::InvalidSignature));
+    }
+
+    #[test]
+    fn missing_signature_is_rejected() {
+        let mut blockchain = sample_blockchain();
+        blockchain.chain[3].signature = None;
+
+        assert_eq!(failure(&blockchain), (3, ChainErrorReason::MissingSignature));
+        let err = Blockchain::from_stored(blockchain.chain.clone(), blockchain.signing_key.clone()).unwrap_err();
+        assert!(err.contains("--sign-chain"), "{}", err);
+    }
+
+    #[test]
+    fn signing_an_unsigned_chain_makes_it_valid() {
+        let mut blockchain = sample_blockchain();
+        for block in blockchain.chain.iter_mut().skip(2).step_by(2) {
+            block.signature = None;
+        }
+        let unsigned = blockchain.chain.iter().filter(|block| block.signature.is_none()).count();
+
+        assert!(blockchain.verify().is_err());
+        assert!(blockchain.verify_unsigned().is_ok());
+
+        assert_eq!(blockchain.sign_unsigned_blocks().len(), unsigned);
+        assert!(blockchain.verify().is_ok());
+        assert!(blockchain.integrity().is_ok());
+    }
+
+    #[test]
+    fn unsigned_chain_with_a_forged_signature_cannot_be_signed() {
+        let mut blockchain = sample_blockchain();
+        blockchain.chain[1].signature = None;
+        blockchain.chain[2].sign(&SigningKey::from_bytes(&[8; 32]));
+
+        assert_eq!(blockchain.verify_unsigned().unwrap_err().reason, ChainErrorReason::InvalidSignature);
+    }
+
+    #[test]
+    fn blocks_after_a_clock_step_back_keep_the_chain_valid() {
+        let mut blockchain = sample_blockchain();
+        let signing_key = blockchain.signing_key.clone();
+
+        // O último bloco ficou com um horário à frente do relógio atual
+        let last = blockchain.chain.last_mut().unwrap();
+        last.timestamp = current_timestamp() + 60 * 60 * 1000;
+        reseal(last, &signing_key);
+        let last_timestamp = last.timestamp;
+        blockchain.integrity = blockchain.verify();
+
+        let block = blockchain.create_election(new_election("Depois", None), "a@x.com".to_string()).unwrap();
+        assert_eq!(block.timestamp, last_timestamp);
+        assert!(blockchain.check_next(&block).is_ok());
+
+        blockchain.commit_block(block);
+        assert!(blockchain.verify().is_ok());
+        assert!(blockchain.integrity().is_ok());
+    }
+
+    #[test]
+    fn browse_pages_of_one_cover_every_election_once_in_order() {
+        let blockchain = sample_blockchain();
+
+        for sort in [ElectionSort::Newest, ElectionSort::Oldest, ElectionSort::Title] {
+            for filter in filters(&blockchain) {
+                let expected = expected(&blockchain, &filter, sort);
+                for limit in [1, 2, 100] {
+                    assert_eq!(browse_all(&blockchain, &filter, sort, limit), expected, "{:?} {:?} limit {}", sort, filter, limit);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn browse_accepts_any_cursor_without_panicking() {
+        let blockchain = sample_blockchain();
+
+        for sort in [ElectionSort::Newest, ElectionSort::Oldest, ElectionSort::Title] {
+            for filter in filters(&blockchain) {
+                blockchain.browse_elections(&filter, sort, Some(usize::MAX), 1);
+            }
+        }
+        let (page, next_cursor) = blockchain.browse_elections(&ElectionFilter::default(), ElectionSort::Oldest, Some(usize::MAX), 1);
+        assert!(page.is_empty());
+        assert_eq!(next_cursor, None);
+    }
+
+    #[test]
+    fn browse_without_filters_returns_every_election() {
+        let blockchain = sample_blockchain();
+
+        let mut found = browse_all(&blockchain, &ElectionFilter::default(), ElectionSort::Title, 1);
+        found.sort();
+        found.dedup();
+        assert_eq!(found.len(), TITLES.len());
+    }
+}
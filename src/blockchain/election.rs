@@ -0,0 +1,239 @@
+use std::collections::{BTreeSet, HashSet};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+// Limites dos textos exibidos ao eleitor. Ficam aqui, e não em `constants`,
+// porque o módulo blockchain também é usado pelo verificador offline.
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_LABEL_LENGTH: usize = 200;
+const MAX_IMAGE_URL_LENGTH: usize = 2048;
+const MIN_SLUG_LENGTH: usize = 3;
+const MAX_SLUG_LENGTH: usize = 64;
+
+// Dados para criação de uma eleição, antes de virar bloco.
+// O id é gerado pelo servidor; o criador só escolhe o slug, se quiser.
+// `options` vem na ordem em que o criador quer exibi-las.
+#[derive(Debug, Clone)]
+pub struct NewElection {
+    pub slug: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub options: Vec<VoteOption>,
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+    pub voting_method: VotingMethod,
+    pub max_selections: Option<u32>,
+    pub eligibility: Eligibility,
+    pub secret_ballot: bool,
+}
+
+impl NewElection {
+    // Confere textos, links e ids das opções; devolve a mensagem do primeiro problema
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(slug) = &self.slug {
+            validate_slug(slug)?;
+        }
+        check_length("title", self.title.as_deref(), MAX_TITLE_LENGTH)?;
+        check_length("description", self.description.as_deref(), MAX_DESCRIPTION_LENGTH)?;
+
+        let mut ids = HashSet::new();
+        for option in &self.options {
+            if option.id.trim().is_empty() {
+                return Err("Vote option id must not be empty".to_string());
+            }
+            if !ids.insert(option.id.as_str()) {
+                return Err(format!("Vote option {} appears more than once", option.id));
+            }
+            if option.label.trim().is_empty() {
+                return Err(format!("Vote option {} must have a label", option.id));
+            }
+            check_length("label", Some(&option.label), MAX_LABEL_LENGTH)?;
+            check_length("description", option.description.as_deref(), MAX_DESCRIPTION_LENGTH)?;
+            check_length("image_url", option.image_url.as_deref(), MAX_IMAGE_URL_LENGTH)?;
+            if let Some(image_url) = &option.image_url {
+                if !image_url.starts_with("https://") && !image_url.starts_with("http://") {
+                    return Err(format!("Vote option {} has an invalid image_url", option.id));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Slug: de 3 a 64 caracteres entre letras minúsculas, dígitos e hífens, sem
+// hífen nas pontas nem repetido. Não pode ter a forma de um UUID, para nunca
+// ser confundido com o id de outra eleição.
+pub fn validate_slug(slug: &str) -> Result<(), String> {
+    let length = slug.chars().count();
+    if !(MIN_SLUG_LENGTH..=MAX_SLUG_LENGTH).contains(&length) {
+        return Err(format!("slug must have between {} and {} characters", MIN_SLUG_LENGTH, MAX_SLUG_LENGTH));
+    }
+    if !slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err("slug may only contain lowercase letters, digits and hyphens".to_string());
+    }
+    if slug.starts_with('-') || slug.ends_with('-') || slug.contains("--") {
+        return Err("slug must not start or end with a hyphen or contain consecutive hyphens".to_string());
+    }
+    if Uuid::parse_str(slug).is_ok() {
+        return Err("slug must not look like an election id".to_string());
+    }
+    Ok(())
+}
+
+fn check_length(field: &str, value: Option<&str>, max: usize) -> Result<(), String> {
+    match value {
+        Some(value) if value.chars().count() > max => Err(format!("{} must have at most {} characters", field, max)),
+        _ => Ok(()),
+    }
+}
+
+// Opção de voto com o que é exibido ao eleitor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VoteOption {
+    pub id: String,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+}
+
+impl VoteOption {
+    // Opção só com id, como nas eleições criadas antes dos rótulos
+    pub fn from_id(id: String) -> Self {
+        VoteOption {
+            label: id.clone(),
+            id,
+            description: None,
+            image_url: None,
+        }
+    }
+}
+
+// Eleição como é apresentada aos clientes, com as opções em ordem fixa
+#[derive(Debug, Clone, Serialize)]
+pub struct Election {
+    pub election_id: String,
+    pub slug: Option<String>,
+    // Horário do bloco que criou a eleição
+    pub created_at: u128,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub options: Vec<VoteOption>,
+}
+
+impl Election {
+    pub fn has_option(&self, option_id: &str) -> bool {
+        self.options.iter().any(|option| option.id == option_id)
+    }
+
+    pub fn option_ids(&self) -> Vec<String> {
+        self.options.iter().map(|option| option.id.clone()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionStatus {
+    Scheduled,
+    Open,
+    Closed,
+}
+
+// Janela de votação de uma eleição, em milissegundos desde a época
+// (mesma unidade do timestamp dos blocos)
+#[derive(Debug, Clone, Default)]
+pub struct ElectionSchedule {
+    pub starts_at: Option<u128>,
+    pub ends_at: Option<u128>,
+    pub closed_at: Option<u128>,
+}
+
+impl ElectionSchedule {
+    pub fn status(&self, now: u128) -> ElectionStatus {
+        if self.closed_at.is_some() {
+            return ElectionStatus::Closed;
+        }
+        if let Some(ends_at) = self.ends_at {
+            if now >= ends_at {
+                return ElectionStatus::Closed;
+            }
+        }
+        if let Some(starts_at) = self.starts_at {
+            if now < starts_at {
+                return ElectionStatus::Scheduled;
+            }
+        }
+        ElectionStatus::Open
+    }
+}
+
+// Forma de votação da eleição. Eleições criadas antes da existência deste
+// campo são de maioria simples (plurality)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingMethod {
+    #[default]
+    Plurality,
+    RankedChoice,
+    Approval,
+}
+
+impl VotingMethod {
+    pub fn is_plurality(&self) -> bool {
+        *self == VotingMethod::Plurality
+    }
+}
+
+// Quem pode votar numa eleição: emails listados explicitamente e/ou qualquer
+// email dos domínios listados. Sem nenhum dos dois, a eleição é aberta a todos.
+// BTreeSet mantém a ordem fixa, para que o hash do bloco seja determinístico.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Eligibility {
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub voters: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub domains: BTreeSet<String>,
+}
+
+impl Eligibility {
+    pub fn new(voters: Vec<String>, domains: Vec<String>) -> Result<Self, String> {
+        Ok(Eligibility {
+            voters: voters.iter().map(|voter| normalize_email(voter)).collect::<Result<_, _>>()?,
+            domains: domains.iter().map(|domain| normalize_domain(domain)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.voters.is_empty() && self.domains.is_empty()
+    }
+
+    // Compara o email do cadastro, que não é confirmado no /signup (ver README)
+    pub fn allows(&self, voter_id: &str) -> bool {
+        if self.is_open() {
+            return true;
+        }
+        let voter_id = voter_id.to_lowercase();
+        self.voters.contains(&voter_id)
+            || voter_id.rsplit_once('@').is_some_and(|(_, domain)| self.domains.contains(domain))
+    }
+}
+
+// Emails são comparados sem diferenciar maiúsculas
+pub fn normalize_email(email: &str) -> Result<String, String> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((user, domain)) if !user.is_empty() && !domain.is_empty() && !domain.contains('@') => Ok(email),
+        _ => Err(format!("Invalid email in eligibility list: {}", email)),
+    }
+}
+
+// Aceita "empresa.com" ou "@empresa.com"
+fn normalize_domain(domain: &str) -> Result<String, String> {
+    let domain = domain.trim().trim_start_matches('@').to_lowercase();
+    if domain.is_empty() || !domain.contains('.') || domain.contains('@') {
+        return Err(format!("Invalid email domain in eligibility list: {}", domain));
+    }
+    Ok(domain)
+}
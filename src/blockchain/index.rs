@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, Range};
+use serde::Deserialize;
+use crate::blockchain::election::ElectionStatus;
+
+// Índices das eleições usados na busca (GET /elections/browse). São
+// atualizados a cada bloco `election_created`, para que a busca não precise
+// percorrer a cadeia. Cada eleição é identificada pela sua posição na ordem
+// de criação, que não muda ao reaplicar a cadeia.
+#[derive(Debug, Default)]
+pub struct ElectionIndex {
+    entries: Vec<IndexEntry>,
+    positions: HashMap<String, usize>,
+    // (título normalizado, posição), para ordenar por título
+    by_title: BTreeSet<(String, usize)>,
+    // Palavra do título ou do slug -> posições das eleições que a contêm
+    words: BTreeMap<String, BTreeSet<usize>>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    election_id: String,
+    // Horário do bloco que criou a eleição; cresce junto com a posição
+    created_at: u128,
+    title_key: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionSort {
+    #[default]
+    Newest,
+    Oldest,
+    Title,
+}
+
+#[derive(Debug, Default)]
+pub struct ElectionFilter {
+    pub status: Option<ElectionStatus>,
+    pub creator: Option<String>,
+    pub created_after: Option<u128>,
+    pub created_before: Option<u128>,
+    pub query: Option<String>,
+}
+
+impl ElectionIndex {
+    pub fn insert(&mut self, election_id: &str, created_at: u128, title: Option<&str>, slug: Option<&str>) {
+        let position = self.entries.len();
+
+        // Sem título, a eleição é ordenada pelo slug ou, na falta dele, pelo id
+        let title_key = title.or(slug).unwrap_or(election_id).to_lowercase();
+
+        for word in title.into_iter().chain(slug).flat_map(words) {
+            self.words.entry(word).or_default().insert(position);
+        }
+        self.by_title.insert((title_key.clone(), position));
+        self.positions.insert(election_id.to_string(), position);
+        self.entries.push(IndexEntry {
+            election_id: election_id.to_string(),
+            created_at,
+            title_key,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn position(&self, election_id: &str) -> Option<usize> {
+        self.positions.get(election_id).copied()
+    }
+
+    pub fn election_id(&self, position: usize) -> Option<&String> {
+        self.entries.get(position).map(|entry| &entry.election_id)
+    }
+
+    // Posições das eleições criadas dentro do intervalo (limites inclusivos)
+    pub fn created_between(&self, after: Option<u128>, before: Option<u128>) -> Range<usize> {
+        let start = after.map_or(0, |after| self.entries.partition_point(|entry| entry.created_at < after));
+        let end = before.map_or(self.entries.len(), |before| self.entries.partition_point(|entry| entry.created_at <= before));
+        start..end.max(start)
+    }
+
+    // Eleições cujo título ou slug tem, para cada termo da busca, uma palavra
+    // que começa com ele. Uma busca sem termos encontra todas.
+    pub fn search(&self, query: &str) -> BTreeSet<usize> {
+        let mut found: Option<BTreeSet<usize>> = None;
+        for term in words(query) {
+            let matches: BTreeSet<usize> = self.words
+                .range(term.clone()..)
+                .take_while(|(word, _)| word.starts_with(&term))
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+            found = Some(match found {
+                Some(found) => found.intersection(&matches).copied().collect(),
+                None => matches,
+            });
+        }
+        found.unwrap_or_else(|| (0..self.entries.len()).collect())
+    }
+
+    // Posições em ordem de título, a partir da seguinte ao cursor
+    pub fn by_title_after(&self, cursor: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        let start = match cursor.and_then(|cursor| self.title_key(cursor).map(|key| (key.clone(), cursor))) {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        self.by_title
+            .range((start, Bound::Unbounded))
+            .map(|(_, position)| *position)
+    }
+
+    pub fn title_key(&self, position: usize) -> Option<&String> {
+        self.entries.get(position).map(|entry| &entry.title_key)
+    }
+}
+
+// Palavras normalizadas de um texto: minúsculas, separadas por qualquer
+// caractere que não seja letra ou dígito
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
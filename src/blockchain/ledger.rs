@@ -0,0 +1,97 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use crate::errors::errors::AppError;
+use crate::blockchain::block::Block;
+use crate::blockchain::blockchain::Blockchain;
+
+// Blockchain compartilhada pelos handlers.
+//
+// `read` devolve a trava de leitura. Enquanto ela é segurada a cadeia não muda
+// e nenhum bloco aparece anexado pela metade; leituras não esperam umas pelas
+// outras. Não é um snapshot: duas leituras seguidas podem ver estados
+// diferentes. A trava é justa, então um `commit` esperando espera as leituras
+// em andamento, e as leituras que chegam depois esperam o `commit`. Por isso
+// os handlers copiam o que precisam, soltam a trava antes de montar a
+// resposta e nunca fazem await com ela; o que é caro, como a verificação
+// completa da cadeia, é feito sobre uma cópia.
+//
+// Escritas passam por um único escritor por vez (`writer`): ele valida e
+// monta o bloco só com a trava de leitura, persiste no banco sem travar
+// ninguém e só pega a trava de escrita para anexar o bloco, o que é rápido.
+// Como só um escritor roda de cada vez, a cadeia não muda entre a validação e
+// o `commit`.
+//
+// As travas do tokio não ficam envenenadas por pânico. Se `commit_block`
+// entrar em pânico no meio da atualização, o estado em memória é refeito a
+// partir dos blocos já anexados e o ledger passa a recusar escritas: o bloco
+// já está no banco, e só um reinício volta a carregá-lo.
+#[derive(Debug)]
+pub struct Ledger {
+    state: RwLock<Blockchain>,
+    writer: Mutex<()>,
+    failed: AtomicBool,
+}
+
+pub type SharedBlockchain = Arc<Ledger>;
+
+// Permissão exclusiva de escrita, obtida com `Ledger::writer`
+pub struct LedgerWriter<'a> {
+    ledger: &'a Ledger,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl Ledger {
+    pub fn new(blockchain: Blockchain) -> Self {
+        Ledger {
+            state: RwLock::new(blockchain),
+            writer: Mutex::new(()),
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, Blockchain> {
+        self.state.read().await
+    }
+
+    // Espera a vez de escrever; falha se uma escrita anterior deixou o ledger inutilizável
+    pub async fn writer(&self) -> Result<LedgerWriter<'_>, AppError> {
+        let guard = self.writer.lock().await;
+        if self.failed.load(Ordering::SeqCst) {
+            return Err(AppError::LedgerUnavailable);
+        }
+        Ok(LedgerWriter { ledger: self, _guard: guard })
+    }
+}
+
+impl LedgerWriter<'_> {
+    pub async fn read(&self) -> RwLockReadGuard<'_, Blockchain> {
+        self.ledger.state.read().await
+    }
+
+    // Confere o bloco contra a cadeia antes de ele ser persistido: um bloco
+    // que a quebraria nunca chega ao banco
+    pub async fn check(&self, block: &Block) -> Result<(), AppError> {
+        self.ledger.state.read().await
+            .check_next(block)
+            .map_err(|err| AppError::Internal(format!("Block rejected before being stored: {}", err)))
+    }
+
+    // Anexa um bloco que já foi persistido no banco
+    pub async fn commit(&self, block: Block) -> Result<(), AppError> {
+        let mut state = self.ledger.state.write().await;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| state.commit_block(block)));
+        if result.is_err() {
+            self.ledger.failed.store(true, Ordering::SeqCst);
+            state.rebuild();
+            return Err(AppError::LedgerUnavailable);
+        }
+        Ok(())
+    }
+
+    pub async fn record_participation(&self, election_id: String, voter_id: String) {
+        self.ledger.state.write().await.record_participation(election_id, voter_id);
+    }
+}
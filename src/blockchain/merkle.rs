@@ -0,0 +1,241 @@
+use serde::Serialize;
+use sha2::{Sha256, Digest};
+
+// Árvore de Merkle sobre os commitments das cédulas de uma eleição, na ordem
+// da cadeia. Folhas e nós internos usam prefixos diferentes (0x00 e 0x01),
+// para que um nó interno nunca possa se passar por uma cédula. Um nó sem par
+// sobe para o nível seguinte sem ser duplicado. A raiz de uma eleição sem
+// cédulas é o hash vazio.
+//
+// Para conferir uma prova: h = leaf_hash(commitment); para cada passo,
+// h = node_hash(passo.hash, h) se side == "left", senão node_hash(h, passo.hash);
+// no fim, h deve ser igual à raiz.
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub side: Side,
+}
+
+pub fn leaf_hash(commitment: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(commitment.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn node_hash(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn empty_root() -> String {
+    format!("{:x}", Sha256::new().finalize())
+}
+
+pub fn merkle_root(commitments: &[String]) -> String {
+    if commitments.is_empty() {
+        return empty_root();
+    }
+
+    let mut level: Vec<String> = commitments.iter().map(|commitment| leaf_hash(commitment)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+// Prova de inclusão da folha `index`, da base até a raiz
+pub fn merkle_proof(commitments: &[String], mut index: usize) -> Option<Vec<ProofStep>> {
+    if index >= commitments.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level: Vec<String> = commitments.iter().map(|commitment| leaf_hash(commitment)).collect();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if let Some(hash) = level.get(sibling) {
+            proof.push(ProofStep {
+                hash: hash.clone(),
+                side: if sibling < index { Side::Left } else { Side::Right },
+            });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+// Árvore mantida cédula a cédula: cada folha nova só recalcula o caminho até
+// a raiz. Dá a mesma raiz e as mesmas provas que `merkle_root` e
+// `merkle_proof` sobre as mesmas folhas.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTree {
+    // levels[0] são as folhas; o último nível só tem a raiz
+    levels: Vec<Vec<String>>,
+}
+
+impl MerkleTree {
+    // Acrescenta a folha e devolve a posição dela
+    pub fn push(&mut self, commitment: &str) -> usize {
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].push(leaf_hash(commitment));
+
+        let mut level = 0;
+        let mut index = self.levels[0].len() - 1;
+        while self.levels[level].len() > 1 {
+            let nodes = &self.levels[level];
+            let parent = match index % 2 {
+                0 => nodes[index].clone(),
+                _ => node_hash(&nodes[index - 1], &nodes[index]),
+            };
+
+            if self.levels.len() == level + 1 {
+                self.levels.push(Vec::new());
+            }
+            let parents = &mut self.levels[level + 1];
+            match parents.get_mut(index / 2) {
+                Some(node) => *node = parent,
+                None => parents.push(parent),
+            }
+
+            level += 1;
+            index /= 2;
+        }
+
+        self.levels[0].len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn root(&self) -> String {
+        match self.levels.last() {
+            Some(top) => top[0].clone(),
+            None => empty_root(),
+        }
+    }
+
+    // Prova de inclusão da folha `index`, da base até a raiz
+    pub fn proof(&self, mut index: usize) -> Option<Vec<ProofStep>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        for nodes in self.levels.iter().take_while(|nodes| nodes.len() > 1) {
+            let sibling = index ^ 1;
+            if let Some(hash) = nodes.get(sibling) {
+                proof.push(ProofStep {
+                    hash: hash.clone(),
+                    side: if sibling < index { Side::Left } else { Side::Right },
+                });
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitments(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("commitment-{}", i)).collect()
+    }
+
+    // Refaz a raiz a partir da folha e da prova, como descrito no topo do arquivo
+    fn root_from_proof(commitment: &str, proof: &[ProofStep]) -> String {
+        proof.iter().fold(leaf_hash(commitment), |hash, step| match step.side {
+            Side::Left => node_hash(&step.hash, &hash),
+            Side::Right => node_hash(&hash, &step.hash),
+        })
+    }
+
+    #[test]
+    fn every_proof_rebuilds_the_root() {
+        for count in [1, 3, 5] {
+            let commitments = commitments(count);
+            let root = merkle_root(&commitments);
+
+            for (index, commitment) in commitments.iter().enumerate() {
+                let proof = merkle_proof(&commitments, index).unwrap();
+                assert_eq!(root_from_proof(commitment, &proof), root, "leaf {} of {}", index, count);
+            }
+            assert!(merkle_proof(&commitments, count).is_none());
+        }
+    }
+
+    #[test]
+    fn single_leaf_root_is_the_leaf_hash() {
+        let commitments = commitments(1);
+
+        assert_eq!(merkle_root(&commitments), leaf_hash(&commitments[0]));
+        assert!(merkle_proof(&commitments, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty_tree_has_the_empty_hash_and_no_proofs() {
+        assert_eq!(merkle_root(&[]), format!("{:x}", Sha256::new().finalize()));
+        assert!(merkle_proof(&[], 0).is_none());
+    }
+
+    #[test]
+    fn incremental_tree_matches_the_full_computation() {
+        let mut tree = MerkleTree::default();
+        assert_eq!(tree.root(), merkle_root(&[]));
+        assert!(tree.proof(0).is_none());
+
+        let commitments = commitments(9);
+        for (count, commitment) in commitments.iter().enumerate() {
+            assert_eq!(tree.push(commitment), count);
+
+            let leaves = &commitments[..=count];
+            assert_eq!(tree.len(), leaves.len());
+            assert_eq!(tree.root(), merkle_root(leaves), "{} leaves", leaves.len());
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof(index).unwrap();
+                assert_eq!(root_from_proof(leaf, &proof), tree.root());
+                assert_eq!(proof.len(), merkle_proof(leaves, index).unwrap().len());
+            }
+        }
+    }
+
+    #[test]
+    fn proof_does_not_rebuild_the_root_for_another_commitment() {
+        let commitments = commitments(5);
+        let proof = merkle_proof(&commitments, 2).unwrap();
+
+        assert_ne!(root_from_proof(&commitments[3], &proof), merkle_root(&commitments));
+    }
+}
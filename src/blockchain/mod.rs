@@ -1,2 +1,10 @@
 pub mod blockchain;
-pub mod block;
\ No newline at end of file
+pub mod block;
+pub mod election;
+pub mod tally;
+pub mod ballot;
+pub mod receipt;
+pub mod merkle;
+pub mod audit;
+pub mod index;
+pub mod ledger;
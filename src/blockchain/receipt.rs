@@ -0,0 +1,50 @@
+use serde::Serialize;
+use sha2::{Sha256, Digest};
+use crate::blockchain::block::{Block, BlockPayload};
+use crate::blockchain::election::ElectionStatus;
+
+// Comprovante entregue ao eleitor depois do voto. `ballot_commitment` é o
+// hash do conteúdo da cédula: o eleitor, que sabe em quem votou, consegue
+// recalculá-lo, mas o comprovante sozinho não revela o voto.
+#[derive(Debug, Serialize)]
+pub struct Receipt {
+    pub block_index: u64,
+    pub block_hash: String,
+    pub ballot_commitment: String,
+}
+
+impl Receipt {
+    pub fn for_block(block: &Block) -> Self {
+        Receipt {
+            block_index: block.index,
+            block_hash: block.hash.clone(),
+            ballot_commitment: ballot_commitment(&block.payload),
+        }
+    }
+}
+
+pub fn ballot_commitment(payload: &BlockPayload) -> String {
+    let encoded = bincode::serialize(payload).unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(&encoded);
+    format!("{:x}", hasher.finalize())
+}
+
+// Resultado da conferência de um comprovante. Não inclui a opção votada.
+#[derive(Debug, Serialize)]
+pub struct ReceiptVerification {
+    pub block_index: u64,
+    pub block_hash: String,
+    pub election_id: String,
+    pub ballot_commitment: String,
+    // A cédula está na cadeia com o mesmo hash e encadeada ao bloco anterior
+    pub included: bool,
+    // O commitment informado pelo eleitor confere com a cédula gravada
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment_matches: Option<bool>,
+    // A cadeia inteira verifica, então a apuração lê exatamente essa cédula
+    pub counted: bool,
+    // A eleição já foi encerrada e a apuração é definitiva
+    pub final_tally: bool,
+    pub election_status: Option<ElectionStatus>,
+}
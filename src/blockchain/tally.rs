@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, HashSet};
+use serde::{Serialize, Deserialize};
+use crate::blockchain::election::VotingMethod;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RunoffRound {
+    pub round: usize,
+    pub counts: BTreeMap<String, usize>,
+    pub exhausted: usize,
+    pub eliminated: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunoffResult {
+    pub rounds: Vec<RunoffRound>,
+    pub winner: Option<String>,
+}
+
+// Apuração por segundo turno instantâneo (instant-runoff).
+//
+// Em cada rodada, cada cédula conta para a opção mais bem colocada que ainda
+// está na disputa; cédulas sem nenhuma opção restante ficam esgotadas. Vence a
+// opção com maioria absoluta das cédulas não esgotadas, ou a última que restar.
+// Caso contrário, a opção com menos votos é eliminada.
+//
+// Desempate na eliminação: entre as opções empatadas com menos votos, elimina a
+// que teve menos votos na rodada anterior mais recente em que houve diferença
+// entre elas; se o empate persistir em todas as rodadas, elimina a de maior id
+// em ordem alfabética.
+pub fn instant_runoff(options: &[String], ballots: &[Vec<String>]) -> RunoffResult {
+    let mut continuing: HashSet<&String> = options.iter().collect();
+    let mut rounds: Vec<RunoffRound> = Vec::new();
+
+    loop {
+        let mut counts: BTreeMap<String, usize> = continuing.iter().map(|option| (option.to_string(), 0)).collect();
+        let mut exhausted = 0;
+
+        for ballot in ballots {
+            match ballot.iter().find(|option| continuing.contains(option)) {
+                Some(option) => *counts.entry(option.clone()).or_default() += 1,
+                None => exhausted += 1,
+            }
+        }
+
+        let round = rounds.len() + 1;
+        let active = ballots.len() - exhausted;
+
+        if active == 0 {
+            rounds.push(RunoffRound { round, counts, exhausted, eliminated: None });
+            return RunoffResult { rounds, winner: None };
+        }
+
+        let (leader, leader_votes) = counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(option, votes)| (option.clone(), *votes))
+            .unwrap();
+
+        if continuing.len() == 1 || leader_votes * 2 > active {
+            rounds.push(RunoffRound { round, counts, exhausted, eliminated: None });
+            return RunoffResult { rounds, winner: Some(leader) };
+        }
+
+        let fewest = *counts.values().min().unwrap();
+        let mut tied: Vec<String> = counts
+            .iter()
+            .filter(|(_, votes)| **votes == fewest)
+            .map(|(option, _)| option.clone())
+            .collect();
+
+        for previous in rounds.iter().rev() {
+            if tied.len() <= 1 {
+                break;
+            }
+            let lowest = tied.iter().map(|option| previous.counts[option]).min().unwrap();
+            tied.retain(|option| previous.counts[option] == lowest);
+        }
+
+        let eliminated = tied.into_iter().max().unwrap();
+        continuing.retain(|option| **option != eliminated);
+        rounds.push(RunoffRound { round, counts, exhausted, eliminated: Some(eliminated) });
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApprovalResult {
+    pub counts: BTreeMap<String, usize>,
+    pub winners: Vec<String>,
+}
+
+// Apuração por aprovação: cada opção marcada na cédula recebe um voto.
+// Vencem todas as opções empatadas com a maior contagem.
+pub fn approval(options: &[String], ballots: &[Vec<String>]) -> ApprovalResult {
+    let mut counts: BTreeMap<String, usize> = options.iter().map(|option| (option.clone(), 0)).collect();
+
+    for ballot in ballots {
+        for option in ballot {
+            *counts.entry(option.clone()).or_default() += 1;
+        }
+    }
+
+    let winners = leaders(&counts);
+    ApprovalResult { counts, winners }
+}
+
+// Opções empatadas com a maior contagem; nenhuma se ninguém recebeu voto
+pub fn leaders(counts: &BTreeMap<String, usize>) -> Vec<String> {
+    let most = counts.values().copied().max().unwrap_or(0);
+    if most == 0 {
+        return Vec::new();
+    }
+    counts.iter().filter(|(_, votes)| **votes == most).map(|(option, _)| option.clone()).collect()
+}
+
+// Contagem de uma eleição mantida a cada cédula registrada (ver
+// Blockchain::commit_block), para que a apuração parcial não precise reler
+// as cédulas. O segundo turno instantâneo não é incremental e continua
+// sendo calculado a partir das cédulas.
+#[derive(Debug, Clone, Default)]
+pub struct RunningTally {
+    pub ballot_count: usize,
+    // Primeira opção de cada cédula: a contagem da maioria simples e as
+    // primeiras preferências de uma eleição ranqueada
+    pub first_choices: BTreeMap<String, usize>,
+    // Toda opção marcada em cada cédula: a contagem da eleição por aprovação
+    pub marks: BTreeMap<String, usize>,
+}
+
+impl RunningTally {
+    pub fn new(options: &[String]) -> Self {
+        let zeros: BTreeMap<String, usize> = options.iter().map(|option| (option.clone(), 0)).collect();
+        RunningTally {
+            ballot_count: 0,
+            first_choices: zeros.clone(),
+            marks: zeros,
+        }
+    }
+
+    pub fn record(&mut self, ballot: &[String]) {
+        self.ballot_count += 1;
+        if let Some(first) = ballot.first() {
+            *self.first_choices.entry(first.clone()).or_default() += 1;
+        }
+        for option in ballot {
+            *self.marks.entry(option.clone()).or_default() += 1;
+        }
+    }
+}
+
+// Apuração completa de uma eleição, no formato do pacote de auditoria; o
+// verify_bundle recalcula esta estrutura a partir das cédulas e compara.
+// `counts` traz as primeiras preferências numa eleição ranqueada.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Tally {
+    pub voting_method: VotingMethod,
+    pub ballot_count: usize,
+    pub counts: BTreeMap<String, usize>,
+    pub winners: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rounds: Vec<RunoffRound>,
+}
+
+pub fn tally(voting_method: VotingMethod, options: &[String], ballots: &[Vec<String>]) -> Tally {
+    match voting_method {
+        VotingMethod::RankedChoice => {
+            let result = instant_runoff(options, ballots);
+            Tally {
+                voting_method,
+                ballot_count: ballots.len(),
+                counts: result.rounds[0].counts.clone(),
+                winners: result.winner.into_iter().collect(),
+                rounds: result.rounds,
+            }
+        }
+        // Na maioria simples cada cédula tem uma opção só, então contar como
+        // aprovação dá o mesmo resultado (empates mantêm todos os vencedores)
+        VotingMethod::Plurality | VotingMethod::Approval => {
+            let result = approval(options, ballots);
+            Tally {
+                voting_method,
+                ballot_count: ballots.len(),
+                counts: result.counts,
+                winners: result.winners,
+                rounds: Vec::new(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn ballots(rankings: &[&str]) -> Vec<Vec<String>> {
+        rankings.iter().map(|ranking| ranking.split(',').map(str::to_string).collect()).collect()
+    }
+
+    #[test]
+    fn instant_runoff_without_ballots_has_no_winner() {
+        let result = instant_runoff(&options(&["a", "b"]), &[]);
+
+        assert_eq!(result.winner, None);
+        assert_eq!(result.rounds.len(), 1);
+        assert_eq!(result.rounds[0].exhausted, 0);
+        assert_eq!(result.rounds[0].eliminated, None);
+    }
+
+    #[test]
+    fn instant_runoff_majority_is_counted_over_ballots_not_exhausted() {
+        // c sai na primeira rodada e a cédula "c" se esgota; na segunda, a e b
+        // empatam desde o início e sai b, a de maior id. a vence com 2 de 2
+        // cédulas ainda ativas, mesmo tendo só 2 de 5 no total.
+        let result = instant_runoff(&options(&["a", "b", "c"]), &ballots(&["a", "a", "b", "b", "c"]));
+
+        let eliminated: Vec<Option<&str>> = result.rounds.iter().map(|round| round.eliminated.as_deref()).collect();
+        assert_eq!(eliminated, vec![Some("c"), Some("b"), None]);
+        assert_eq!(result.rounds[1].exhausted, 1);
+        assert_eq!(result.rounds[2].exhausted, 3);
+        assert_eq!(result.winner, Some("a".to_string()));
+    }
+
+    #[test]
+    fn instant_runoff_tie_uses_most_recent_differing_round() {
+        // Rodada 1: a 4, b 2, c 3, d 1 -> sai d, cujo voto vai para b.
+        // Rodada 2: a 4, b 3, c 3 -> b e c empatam; na rodada 1 b tinha menos,
+        // então sai b, embora a regra do maior id eliminasse c.
+        let result = instant_runoff(
+            &options(&["a", "b", "c", "d"]),
+            &ballots(&["a", "a", "a", "a", "b", "b", "c", "c", "c", "d,b"]),
+        );
+
+        assert_eq!(result.rounds[0].eliminated.as_deref(), Some("d"));
+        assert_eq!(result.rounds[1].counts["b"], 3);
+        assert_eq!(result.rounds[1].counts["c"], 3);
+        assert_eq!(result.rounds[1].eliminated.as_deref(), Some("b"));
+        assert_eq!(result.rounds[2].exhausted, 3);
+        assert_eq!(result.winner, Some("a".to_string()));
+    }
+
+    #[test]
+    fn instant_runoff_tie_in_every_round_eliminates_highest_id() {
+        let result = instant_runoff(&options(&["a", "b"]), &ballots(&["a", "b"]));
+
+        assert_eq!(result.rounds[0].eliminated.as_deref(), Some("b"));
+        assert_eq!(result.winner, Some("a".to_string()));
+    }
+}
@@ -0,0 +1,38 @@
+use std::env;
+
+pub const USAGE: &str = "Usage: rust-crud-api [--migrate | --rollback [STEPS] | --make-admin EMAIL | --sign-chain | --generate-signing-key]
+
+  (no flags)          apply pending migrations and start the HTTP server
+  --migrate           apply pending migrations and exit
+  --rollback [STEPS]  roll back the last STEPS migrations (default 1) and exit
+  --make-admin EMAIL  give the admin role to an existing user and exit
+  --sign-chain        sign blocks stored before block signing existed and exit
+  --generate-signing-key
+                      print a new random SIGNING_KEY and exit";
+
+pub enum Command {
+    Serve,
+    Migrate,
+    Rollback(usize),
+    MakeAdmin(String),
+    SignChain,
+    GenerateSigningKey,
+}
+
+pub fn parse_args() -> Result<Command, String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] => Ok(Command::Serve),
+        ["--migrate"] => Ok(Command::Migrate),
+        ["--rollback"] => Ok(Command::Rollback(1)),
+        ["--rollback", steps] => match steps.parse::<usize>() {
+            Ok(steps) if steps > 0 => Ok(Command::Rollback(steps)),
+            _ => Err(format!("Invalid number of steps: {}", steps)),
+        },
+        ["--make-admin", email] => Ok(Command::MakeAdmin(email.to_string())),
+        ["--sign-chain"] => Ok(Command::SignChain),
+        ["--generate-signing-key"] => Ok(Command::GenerateSigningKey),
+        _ => Err(format!("Unknown arguments: {}", args.join(" "))),
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use ed25519_dalek::SigningKey;
+
+use crate::constants::constants::{
+    DEFAULT_BIND_ADDRESS, DEFAULT_DB_POOL_MAX_SIZE, DEFAULT_DB_POOL_TIMEOUT_SECS, DEFAULT_MAX_VOTE_OPTIONS,
+    DEFAULT_REFRESH_TOKEN_LIFETIME_SECS, DEFAULT_TOKEN_LIFETIME_SECS, MIN_JWT_SECRET_LEN,
+};
+
+// Configuração da aplicação, lida uma única vez na inicialização.
+//
+// Cada valor vem da variável de ambiente de mesmo nome ou, se ela não existir,
+// do arquivo apontado por CONFIG_FILE (linhas KEY=VALUE, `#` para comentários).
+//
+//   DATABASE_URL         obrigatória
+//   JWT_SECRET           obrigatória, com pelo menos 32 caracteres
+//   SIGNING_KEY          obrigatória; semente Ed25519 de 32 bytes em hexadecimal
+//                        (gere uma com --generate-signing-key)
+//   BIND_ADDRESS         padrão 0.0.0.0:8080
+//   TOKEN_LIFETIME_SECS  padrão 3600
+//   REFRESH_TOKEN_LIFETIME_SECS  padrão 2592000 (30 dias)
+//   CORS_ORIGINS         lista separada por vírgulas; padrão `*` (qualquer origem)
+//   MAX_VOTE_OPTIONS     padrão 20
+//   DB_POOL_MAX_SIZE     padrão 16 conexões
+//   DB_POOL_TIMEOUT_SECS padrão 5; tempo máximo de espera por uma conexão livre
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub jwt_secret: String,
+    pub signing_key: SigningKey,
+    pub bind_address: SocketAddr,
+    pub token_lifetime_secs: u64,
+    pub refresh_token_lifetime_secs: u64,
+    pub cors_origins: Vec<String>,
+    pub max_vote_options: usize,
+    pub db_pool_max_size: usize,
+    pub db_pool_timeout_secs: u64,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// Configuração carregada em `init`; chamar antes disso é erro de programação
+pub fn config() -> &'static Config {
+    CONFIG.get().expect("Configuration not initialized")
+}
+
+pub fn init() -> Result<&'static Config, String> {
+    let config = Config::load()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let file_values = match env::var("CONFIG_FILE") {
+            Ok(path) => read_config_file(&path)?,
+            Err(_) => HashMap::new(),
+        };
+        let get = |key: &str| env::var(key).ok().or_else(|| file_values.get(key).cloned());
+
+        let mut missing = Vec::new();
+        let database_url = get("DATABASE_URL").unwrap_or_else(|| { missing.push("DATABASE_URL"); String::new() });
+        let jwt_secret = get("JWT_SECRET").unwrap_or_else(|| { missing.push("JWT_SECRET"); String::new() });
+        let signing_key = get("SIGNING_KEY").unwrap_or_else(|| { missing.push("SIGNING_KEY"); String::new() });
+        if !missing.is_empty() {
+            return Err(format!("Missing required configuration: {}", missing.join(", ")));
+        }
+
+        if database_url.parse::<tokio_postgres::Config>().is_err() {
+            return Err("DATABASE_URL is not a valid Postgres connection string".to_string());
+        }
+
+        if jwt_secret.len() < MIN_JWT_SECRET_LEN {
+            return Err(format!("JWT_SECRET must be at least {} characters long", MIN_JWT_SECRET_LEN));
+        }
+
+        let signing_key = parse_signing_key(&signing_key)?;
+
+        let bind_address = get("BIND_ADDRESS")
+            .unwrap_or_else(|| DEFAULT_BIND_ADDRESS.to_string())
+            .parse::<SocketAddr>()
+            .map_err(|_| "BIND_ADDRESS must be in the form host:port, e.g. 0.0.0.0:8080".to_string())?;
+
+        let token_lifetime_secs = parse_positive(get("TOKEN_LIFETIME_SECS"), "TOKEN_LIFETIME_SECS", DEFAULT_TOKEN_LIFETIME_SECS)?;
+        let refresh_token_lifetime_secs = parse_positive(
+            get("REFRESH_TOKEN_LIFETIME_SECS"),
+            "REFRESH_TOKEN_LIFETIME_SECS",
+            DEFAULT_REFRESH_TOKEN_LIFETIME_SECS,
+        )?;
+        let max_vote_options = parse_positive(get("MAX_VOTE_OPTIONS"), "MAX_VOTE_OPTIONS", DEFAULT_MAX_VOTE_OPTIONS as u64)? as usize;
+        let db_pool_max_size = parse_positive(get("DB_POOL_MAX_SIZE"), "DB_POOL_MAX_SIZE", DEFAULT_DB_POOL_MAX_SIZE as u64)? as usize;
+        let db_pool_timeout_secs = parse_positive(get("DB_POOL_TIMEOUT_SECS"), "DB_POOL_TIMEOUT_SECS", DEFAULT_DB_POOL_TIMEOUT_SECS)?;
+
+        let cors_origins: Vec<String> = get("CORS_ORIGINS")
+            .unwrap_or_else(|| "*".to_string())
+            .split(',')
+            .map(|origin| origin.trim().to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        if cors_origins.is_empty() {
+            return Err("CORS_ORIGINS must list at least one origin, or `*`".to_string());
+        }
+
+        Ok(Config {
+            database_url,
+            jwt_secret,
+            signing_key,
+            bind_address,
+            token_lifetime_secs,
+            refresh_token_lifetime_secs,
+            cors_origins,
+            max_vote_options,
+            db_pool_max_size,
+            db_pool_timeout_secs,
+        })
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.cors_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+fn parse_positive(value: Option<String>, key: &str, default: u64) -> Result<u64, String> {
+    match value {
+        None => Ok(default),
+        Some(value) => match value.trim().parse::<u64>() {
+            Ok(parsed) if parsed > 0 => Ok(parsed),
+            _ => Err(format!("{} must be a positive integer", key)),
+        },
+    }
+}
+
+fn parse_signing_key(value: &str) -> Result<SigningKey, String> {
+    let seed: [u8; 32] = hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "SIGNING_KEY must be 64 hexadecimal characters (a 32-byte Ed25519 seed)".to_string())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn read_config_file(path: &str) -> Result<HashMap<String, String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read config file {}: {}", path, e))?;
+
+    let mut values = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}:{}: expected KEY=VALUE", path, number + 1))?;
+        let value = value.trim().trim_matches('"');
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    Ok(values)
+}
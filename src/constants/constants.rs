@@ -1,3 +1,14 @@
-pub const DB_URL: &str = "postgres://postgres:postgres@db:5432/postgres";
-// Define a SECRET_KEY como uma fatia de bytes (`&[u8]`)
-pub const SECRET_KEY: &[u8] = b"ddd12sdqweqdsncnuNSANDKSMAqm123";
+// Valores padrão usados quando a configuração não define outro
+pub const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:8080";
+pub const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 60 * 60; // 1 hora de validade
+pub const DEFAULT_REFRESH_TOKEN_LIFETIME_SECS: u64 = 30 * 24 * 60 * 60; // 30 dias
+pub const DEFAULT_MAX_VOTE_OPTIONS: usize = 20;
+pub const DEFAULT_DB_POOL_MAX_SIZE: usize = 16;
+pub const DEFAULT_DB_POOL_TIMEOUT_SECS: u64 = 5;
+// Tamanho da página na busca de eleições, quando o cliente não pede outro, e o máximo aceito
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+// Tamanho mínimo do segredo usado para assinar os JWTs
+pub const MIN_JWT_SECRET_LEN: usize = 32;
+// Intervalo entre as rodadas que registram o encerramento das eleições que terminaram pelo horário
+pub const AUTO_CLOSE_INTERVAL_SECS: u64 = 30;
@@ -0,0 +1,115 @@
+use actix_web::{get, put, delete, web, HttpResponse};
+use deadpool_postgres::{GenericClient, Pool};
+
+use crate::auth::auth::AuthenticatedUser;
+use crate::errors::errors::AppError;
+use crate::models::models::{Role, RoleData, UserInfo};
+
+// Garante que sempre reste pelo menos um admin. Os admins ficam travados
+// (FOR UPDATE) até o fim da transação, para que duas remoções simultâneas
+// não deixem o sistema sem nenhum.
+async fn ensure_not_last_admin<C: GenericClient>(client: &C, user_id: i32) -> Result<(), AppError> {
+    let admins: Vec<i32> = client.query(
+        "SELECT id FROM users WHERE role = 'admin' FOR UPDATE",
+        &[],
+    ).await?.iter().map(|row| row.get(0)).collect();
+
+    if admins == [user_id] {
+        return Err(AppError::LastAdmin);
+    }
+
+    Ok(())
+}
+
+#[get("/admin/users")]
+async fn handle_get_users(
+    user: AuthenticatedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, AppError> {
+    user.require_role(&[Role::Admin])?;
+
+    let client = pool.get().await?;
+
+    let rows = client.query(
+        "SELECT id, name, email, role FROM users ORDER BY id",
+        &[],
+    ).await?;
+
+    let users = rows.iter()
+        .map(|row| Ok(UserInfo {
+            id: row.get(0),
+            name: row.get(1),
+            email: row.get(2),
+            role: row.get::<_, String>(3).parse().map_err(AppError::Internal)?,
+        }))
+        .collect::<Result<Vec<UserInfo>, AppError>>()?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+#[put("/admin/users/{user_id}/role")]
+async fn handle_put_user_role(
+    user: AuthenticatedUser,
+    pool: web::Data<Pool>,
+    path: web::Path<i32>,
+    web::Json(payload): web::Json<RoleData>,
+) -> Result<HttpResponse, AppError> {
+    user.require_role(&[Role::Admin])?;
+    let user_id = path.into_inner();
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+
+    if payload.role != Role::Admin {
+        ensure_not_last_admin(&transaction, user_id).await?;
+    }
+
+    // Os tokens já emitidos param de valer: o papel faz parte das claims
+    let updated = transaction.execute(
+        "UPDATE users SET role = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2",
+        &[&payload.role.as_str(), &user_id],
+    ).await?;
+
+    if updated == 0 {
+        return Err(AppError::UserNotFound);
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json("Role updated"))
+}
+
+#[delete("/admin/users/{user_id}")]
+async fn handle_delete_user(
+    user: AuthenticatedUser,
+    pool: web::Data<Pool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    user.require_role(&[Role::Admin])?;
+    let user_id = path.into_inner();
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+
+    ensure_not_last_admin(&transaction, user_id).await?;
+
+    // Os votos e eleições do usuário continuam na blockchain; só o acesso é removido
+    transaction.execute("DELETE FROM refresh_tokens WHERE user_id = $1", &[&user_id]).await?;
+    transaction.execute("DELETE FROM sessions WHERE user_id = $1", &[&user_id]).await?;
+    let deleted = transaction.execute("DELETE FROM users WHERE id = $1", &[&user_id]).await?;
+
+    if deleted == 0 {
+        return Err(AppError::UserNotFound);
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json("User deleted"))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(handle_get_users)
+        .service(handle_put_user_role)
+        .service(handle_delete_user);
+}
@@ -0,0 +1,124 @@
+use actix_web::{get, web, HttpResponse};
+
+use crate::auth::auth::AuthenticatedUser;
+use crate::blockchain::blockchain::verify_blocks;
+use crate::blockchain::ledger::SharedBlockchain;
+use crate::errors::errors::AppError;
+use crate::models::models::Role;
+
+#[derive(Deserialize)]
+struct ReceiptQuery {
+    commitment: Option<String>,
+}
+
+// Verificação completa da cadeia, conferindo a assinatura de cada bloco.
+// Só para admins: é cara, e o resultado mantido a cada bloco já responde
+// `counted` nos comprovantes.
+#[get("/chain/verify")]
+async fn handle_get_verify_chain(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+) -> Result<HttpResponse, AppError> {
+    user.require_role(&[Role::Admin])?;
+
+    // Copiar os blocos é rápido; as assinaturas são conferidas já sem o ledger,
+    // para não atrasar os votos
+    let blockchain = blockchain.read().await;
+    let chain = blockchain.chain.clone();
+    let verifying_key = blockchain.verifying_key();
+    drop(blockchain);
+
+    match verify_blocks(&chain, &verifying_key) {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "valid": true,
+            "length": chain.len(),
+        }))),
+        Err(err) => {
+            println!("Chain verification failed at {}", err);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "valid": false,
+                "length": chain.len(),
+                "broken_index": err.index,
+                "reason": err.reason,
+                "message": err.to_string(),
+            })))
+        }
+    }
+}
+
+// Confere um comprovante de voto. Público: a resposta diz se a cédula está na
+// cadeia sem alteração e se entra na apuração, mas nunca em quem ela vota.
+#[get("/receipt/{block_hash}/verify")]
+async fn handle_get_verify_receipt(
+    blockchain: web::Data<SharedBlockchain>,
+    path: web::Path<String>,
+    query: web::Query<ReceiptQuery>,
+) -> Result<HttpResponse, AppError> {
+    let blockchain = blockchain.read().await;
+
+    let verification = blockchain
+        .verify_receipt(&path.into_inner(), query.commitment.as_deref())
+        .ok_or(AppError::ReceiptNotFound)?;
+    drop(blockchain);
+
+    Ok(HttpResponse::Ok().json(verification))
+}
+
+// Prova de inclusão da cédula do comprovante na árvore de Merkle da eleição
+#[get("/receipt/{block_hash}/proof")]
+async fn handle_get_receipt_proof(
+    blockchain: web::Data<SharedBlockchain>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let blockchain = blockchain.read().await;
+
+    let proof = blockchain
+        .ballot_proof(&path.into_inner())
+        .ok_or(AppError::ReceiptNotFound)?;
+    drop(blockchain);
+
+    Ok(HttpResponse::Ok().json(proof))
+}
+
+#[get("/election/{election_id}/merkle-root")]
+async fn handle_get_merkle_root(
+    blockchain: web::Data<SharedBlockchain>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let election_id = path.into_inner();
+
+    let blockchain = blockchain.read().await;
+
+    let election_id = blockchain.resolve_election_id(&election_id).ok_or(AppError::ElectionNotFound)?;
+
+    let (root, published, ballot_count) = blockchain.election_merkle_root(&election_id);
+    drop(blockchain);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "election_id": election_id,
+        "root": root,
+        "published": published,
+        "ballot_count": ballot_count,
+    })))
+}
+
+// Chave pública Ed25519 que confere a assinatura de cada bloco
+#[get("/chain/pubkey")]
+async fn handle_get_public_key(
+    blockchain: web::Data<SharedBlockchain>,
+) -> HttpResponse {
+    let public_key = blockchain.read().await.verifying_key_hex();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "algorithm": "ed25519",
+        "public_key": public_key,
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(handle_get_verify_chain)
+        .service(handle_get_public_key)
+        .service(handle_get_verify_receipt)
+        .service(handle_get_receipt_proof)
+        .service(handle_get_merkle_root);
+}
@@ -1,2 +1,4 @@
 pub mod users;
-pub mod votation;
\ No newline at end of file
+pub mod votation;
+pub mod chain;
+pub mod admin;
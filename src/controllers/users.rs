@@ -1,36 +1,20 @@
-use actix_web::{web, HttpResponse, Responder};
-use tokio_postgres::NoTls;
-use jsonwebtoken::{encode, Header, EncodingKey};
-use std::time::{SystemTime, UNIX_EPOCH};
-use serde::Serialize;
+use actix_web::{web, HttpRequest, HttpResponse};
+use deadpool_postgres::Pool;
+use uuid::Uuid;
 
 use crate::utils::utils::{ hash_password, verify_password };
-use crate::models::models::{SignupData, Claims, LoginData, AuthData};
-use crate::constants::constants::{ DB_URL, SECRET_KEY };
+use crate::models::models::{SignupData, LoginData, AuthData, RefreshData, SessionInfo, Role};
+use crate::auth::auth::{issue_tokens, hash_refresh_token, revoke_family, AuthenticatedUser};
+use crate::errors::errors::AppError;
 
-#[derive(Serialize)]
-struct TokenResponse {
-    token: String,
-}
-
-pub async fn handle_post_signup(body: web::Json<SignupData>) -> impl Responder {
+pub async fn handle_post_signup(pool: web::Data<Pool>, body: web::Json<SignupData>) -> Result<HttpResponse, AppError> {
     let signup_data = body.into_inner();
 
     // Conectar ao banco de dados
-    let (client, connection) = match tokio_postgres::connect(DB_URL, NoTls).await {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Error connecting to database"),
-    };
-
-    // Executa a conexão em uma tarefa separada
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {:?}", e);
-        }
-    });
+    let client = pool.get().await?;
 
     // Hash da senha
-    let hashed_password = hash_password(&signup_data.password); 
+    let hashed_password = hash_password(&signup_data.password);
 
 
     // Inserir o usuário no banco de dados
@@ -40,122 +24,188 @@ pub async fn handle_post_signup(body: web::Json<SignupData>) -> impl Responder {
     ).await;
 
     match result {
-        Ok(_) => HttpResponse::Ok().body("New user created"),
+        Ok(_) => Ok(HttpResponse::Ok().body("New user created")),
         Err(err) => {
             // Tratar erros de banco de dados
-            if let Some(pq_err) = err.as_db_error() {
-                if pq_err.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION {
-                    HttpResponse::BadRequest().body("Email already exists")
-                } else {
-                    HttpResponse::InternalServerError().body(format!("Database error: {}", pq_err))
+            match err.as_db_error() {
+                Some(pq_err) if pq_err.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION => {
+                    Err(AppError::EmailAlreadyExists)
                 }
-            } else {
-                HttpResponse::InternalServerError().body("Unknown database error")
+                _ => Err(AppError::Database(err)),
             }
         }
     }
 }
 
-pub async fn handle_login_request(body: web::Json<LoginData>) -> impl Responder {
+pub async fn handle_login_request(req: HttpRequest, pool: web::Data<Pool>, body: web::Json<LoginData>) -> Result<HttpResponse, AppError> {
     let login_data = body.into_inner();
 
     // Conectar ao banco de dados
-    let (client, connection) = match tokio_postgres::connect(DB_URL, NoTls).await {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Error connecting to database"),
-    };
-
-    // Executa a conexão em uma tarefa separada
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {:?}", e);
-        }
-    });
+    let client = pool.get().await?;
 
     // Consultar o usuário
-    let row = match client.query_one(
-        "SELECT id, password FROM users WHERE email = $1",
+    let row = client.query_opt(
+        "SELECT id, password, role FROM users WHERE email = $1",
         &[&login_data.email],
-    ).await {
-        Ok(row) => row,
-        Err(_) => return HttpResponse::Unauthorized().body("Invalid credentials"),
-    };
+    ).await?.ok_or(AppError::InvalidCredentials)?;
 
     let stored_password: String = row.get(1);
     let user_id: i32 = row.get(0);
+    let role: Role = row.get::<_, String>(2).parse().map_err(AppError::Internal)?;
 
     if !verify_password(&login_data.password, &stored_password) {
-        return HttpResponse::Unauthorized().body("Invalid credentials");
+        return Err(AppError::InvalidCredentials);
     }
 
-    // Deletar sessões anteriores
-    if let Err(e) = client.execute(
-        "DELETE FROM sessions WHERE user_id = $1",
+    // Deletar sessões expiradas; as ativas continuam valendo em outros dispositivos
+    client.execute(
+        "DELETE FROM sessions WHERE user_id = $1 AND expires_at <= CURRENT_TIMESTAMP",
         &[&user_id],
-    ).await {
-        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    ).await?;
+
+    // Cada login começa uma nova família de refresh tokens
+    let family_id = Uuid::new_v4().to_string();
+    let tokens = issue_tokens(&client, &req, user_id, &login_data.email, role, &family_id).await?;
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+// Troca um refresh token válido por um novo par de tokens. Cada refresh token
+// só pode ser usado uma vez: apresentar de novo um token já trocado indica
+// vazamento, e a família inteira é revogada.
+pub async fn handle_refresh_token(req: HttpRequest, pool: web::Data<Pool>, body: web::Json<RefreshData>) -> Result<HttpResponse, AppError> {
+    let refresh_data = body.into_inner();
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+
+    // FOR UPDATE serializa duas trocas simultâneas do mesmo token
+    let row = transaction.query_opt(
+        "SELECT refresh_tokens.id, refresh_tokens.user_id, refresh_tokens.family_id, users.email, users.role,
+                refresh_tokens.used_at IS NOT NULL,
+                refresh_tokens.revoked_at IS NOT NULL OR refresh_tokens.expires_at <= CURRENT_TIMESTAMP
+         FROM refresh_tokens
+         JOIN users ON users.id = refresh_tokens.user_id
+         WHERE refresh_tokens.token_hash = $1
+         FOR UPDATE OF refresh_tokens",
+        &[&hash_refresh_token(&refresh_data.refresh_token)],
+    ).await?.ok_or(AppError::InvalidRefreshToken)?;
+
+    let token_id: i32 = row.get(0);
+    let user_id: i32 = row.get(1);
+    let family_id: String = row.get(2);
+    let email: String = row.get(3);
+    let role: Role = row.get::<_, String>(4).parse().map_err(AppError::Internal)?;
+    let already_used: bool = row.get(5);
+    let unusable: bool = row.get(6);
+
+    if already_used {
+        revoke_family(&transaction, &family_id).await?;
+        transaction.commit().await?;
+        return Err(AppError::RefreshTokenReused);
     }
 
-    // Gerar token JWT
-    let now = SystemTime::now().duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs() as usize;
-    let expiration = now + 60 * 60; // 1 hora de validade
-    let claims = Claims {
-        sub: login_data.email.clone(),
-        iat: now,
-        exp: expiration,
-    };
-
-    let token = match encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET_KEY)) {
-        Ok(t) => t,
-        Err(_) => return HttpResponse::InternalServerError().body("Token generation error"),
-    };
-
-    // Inserir nova sessão
-    if let Err(e) = client.execute(
-        "INSERT INTO sessions (user_id, token) VALUES ($1, $2)",
-        &[&user_id, &token],
-    ).await {
-        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    if unusable {
+        return Err(AppError::InvalidRefreshToken);
     }
 
-    HttpResponse::Ok().json(TokenResponse { token })
+    transaction.execute(
+        "UPDATE refresh_tokens SET used_at = CURRENT_TIMESTAMP WHERE id = $1",
+        &[&token_id],
+    ).await?;
+
+    let tokens = issue_tokens(&transaction, &req, user_id, &email, role, &family_id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(tokens))
 }
 
-pub async fn handle_auth_request(body: web::Json<AuthData>) -> impl Responder {
+pub async fn handle_auth_request(pool: web::Data<Pool>, body: web::Json<AuthData>) -> Result<HttpResponse, AppError> {
     let auth_data = body.into_inner();
 
     // Conectar ao banco de dados
-    let (client, connection) = match tokio_postgres::connect(DB_URL, NoTls).await {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Error connecting to database"),
-    };
-
-    // Executa a conexão em uma tarefa separada
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {:?}", e);
-        }
-    });
+    let client = pool.get().await?;
 
     // Consultar a sessão
-    let row = match client.query_one(
-        "SELECT user_id FROM sessions WHERE token = $1",
+    let row = client.query_opt(
+        "SELECT user_id FROM sessions
+         WHERE token = $1 AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)",
         &[&auth_data.token],
-    ).await {
-        Ok(row) => row,
-        Err(_) => return HttpResponse::Unauthorized().body("Invalid token"),
-    };
+    ).await?.ok_or(AppError::InvalidToken)?;
 
     let user_id: i32 = row.get(0);
 
     // Consultar o usuário
-    match client.query_one(
+    match client.query_opt(
         "SELECT 1 FROM users WHERE id = $1",
         &[&user_id],
-    ).await {
-        Ok(_) => HttpResponse::Ok().body("Token valid"),
-        Err(_) => HttpResponse::NotFound().body("Invalid token"),
+    ).await? {
+        Some(_) => Ok(HttpResponse::Ok().body("Token valid")),
+        None => Err(AppError::InvalidToken),
     }
-}
\ No newline at end of file
+}
+
+// Encerra a sessão do token usado na requisição, junto com o refresh token
+// que a acompanha, para que ela não possa ser renovada
+pub async fn handle_logout(user: AuthenticatedUser, pool: web::Data<Pool>) -> Result<HttpResponse, AppError> {
+    let client = pool.get().await?;
+
+    let row = client.query_opt(
+        "DELETE FROM sessions WHERE token = $1 RETURNING family_id",
+        &[&user.token],
+    ).await?;
+
+    if let Some(family_id) = row.and_then(|row| row.get::<_, Option<String>>(0)) {
+        revoke_family(&client, &family_id).await?;
+    }
+
+    Ok(HttpResponse::Ok().json("Logged out"))
+}
+
+// Encerra todas as sessões do usuário, inclusive a atual
+pub async fn handle_logout_all(user: AuthenticatedUser, pool: web::Data<Pool>) -> Result<HttpResponse, AppError> {
+    let client = pool.get().await?;
+
+    let revoked = client.execute(
+        "DELETE FROM sessions WHERE user_id = $1",
+        &[&user.user_id],
+    ).await?;
+
+    client.execute(
+        "UPDATE refresh_tokens SET revoked_at = CURRENT_TIMESTAMP
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        &[&user.user_id],
+    ).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "revoked_sessions": revoked })))
+}
+
+pub async fn handle_get_sessions(user: AuthenticatedUser, pool: web::Data<Pool>) -> Result<HttpResponse, AppError> {
+    let client = pool.get().await?;
+
+    let rows = client.query(
+        "SELECT id,
+                (EXTRACT(EPOCH FROM created_at) * 1000)::BIGINT,
+                (EXTRACT(EPOCH FROM expires_at) * 1000)::BIGINT,
+                user_agent,
+                ip_address,
+                token = $2
+         FROM sessions
+         WHERE user_id = $1 AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+         ORDER BY created_at DESC",
+        &[&user.user_id, &user.token],
+    ).await?;
+
+    let sessions: Vec<SessionInfo> = rows.iter()
+        .map(|row| SessionInfo {
+            id: row.get(0),
+            created_at: row.get(1),
+            expires_at: row.get(2),
+            user_agent: row.get(3),
+            ip_address: row.get(4),
+            current: row.get(5),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
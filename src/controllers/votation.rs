@@ -1,117 +1,422 @@
-use actix_web::web::service;
-use actix_web::{post, get, web, HttpResponse, HttpRequest};
-use sha2::digest::typenum::Integer;
-use std::collections::HashSet;
-use jsonwebtoken::{decode, Validation, DecodingKey};
+use actix_web::{post, get, delete, web, HttpResponse, HttpRequest};
 use std::collections::HashMap;
-
-use crate::blockchain::blockchain::SharedBlockchain;
-use crate::models::models::Claims;
-use crate::constants::constants::SECRET_KEY;
+use deadpool_postgres::{GenericClient, Pool};
+
+use crate::blockchain::block::BlockPayload;
+use crate::blockchain::blockchain::Blockchain;
+use crate::blockchain::ledger::SharedBlockchain;
+use crate::blockchain::index::{ElectionFilter, ElectionSort};
+use crate::blockchain::receipt::Receipt;
+use crate::blockchain::election::{Eligibility, ElectionStatus, NewElection, VoteOption, VotingMethod};
+use crate::blockchain::tally::{instant_runoff, leaders};
+use crate::config::config::config;
+use crate::constants::constants::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::auth::auth::{authenticate, AuthenticatedUser};
+use crate::database::database::append_block;
+use crate::errors::errors::AppError;
+use crate::models::models::Role;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateElectionPayload {
-    election_id: String,
-    vote_options: HashSet<String>,
+    // O id é gerado pelo servidor; o slug é um nome legível opcional,
+    // aceito no lugar do id em todas as rotas
+    slug: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    // Opções na ordem em que devem ser exibidas
+    vote_options: Vec<VoteOptionPayload>,
+    starts_at: Option<u64>,
+    ends_at: Option<u64>,
+    #[serde(default)]
+    voting_method: VotingMethod,
+    max_selections: Option<u32>,
+    // Lista de aptos a votar: emails, ids de usuário e/ou domínios de email.
+    // Tudo vazio deixa a eleição aberta a qualquer usuário.
+    #[serde(default)]
+    eligible_voters: Vec<String>,
+    #[serde(default)]
+    eligible_user_ids: Vec<i32>,
+    #[serde(default)]
+    eligible_domains: Vec<String>,
+    // Voto secreto: a cadeia não guarda quem votou em quê
+    #[serde(default)]
+    secret_ballot: bool,
 }
 
+// Uma opção pode vir só como id, como antes, ou com rótulo, descrição e imagem
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum VoteOptionPayload {
+    Id(String),
+    Detailed {
+        id: String,
+        label: Option<String>,
+        description: Option<String>,
+        image_url: Option<String>,
+    },
+}
+
+impl From<VoteOptionPayload> for VoteOption {
+    fn from(payload: VoteOptionPayload) -> Self {
+        match payload {
+            VoteOptionPayload::Id(id) => VoteOption::from_id(id),
+            VoteOptionPayload::Detailed { id, label, description, image_url } => VoteOption {
+                label: label.unwrap_or_else(|| id.clone()),
+                id,
+                description: non_empty(description),
+                image_url: non_empty(image_url),
+            },
+        }
+    }
+}
+
+// Textos opcionais em branco contam como ausentes
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EligibleVotersPayload {
+    #[serde(default)]
+    voters: Vec<String>,
+    #[serde(default)]
+    user_ids: Vec<i32>,
+}
+
+// `vote_option_ids` traz a cédula ranqueada, em ordem de preferência, ou as
+// opções aprovadas numa eleição por aprovação
 #[derive(Debug, Deserialize)]
 pub struct VotePayload {
     election_id: String,
-    vote_option_id: String,
+    vote_option_id: Option<String>,
+    vote_option_ids: Option<Vec<String>>,
 }
 
+// `voter_id` só funciona para votos abertos; o voto secreto é encontrado pelo
+// cabeçalho `X-Ballot-Secret` (ver `ballot_secrets`)
 #[derive(Deserialize)]
 struct ElectionQuery {
     election_id: Option<String>,
     voter_id: Option<String>,
 }
 
+// Filtros da busca de eleições. `status` aceita "draft" como sinônimo de
+// "scheduled"; as datas são em milissegundos desde a época; `q` procura
+// palavras do título e do slug
+#[derive(Deserialize)]
+struct BrowseQuery {
+    status: Option<String>,
+    creator: Option<String>,
+    created_after: Option<u64>,
+    created_before: Option<u64>,
+    q: Option<String>,
+    #[serde(default)]
+    sort: ElectionSort,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
 #[derive(Deserialize)]
 struct ResultsQuery {
     election_id: Option<String>
 }
 
-fn extract_user_id_from_token(req: &HttpRequest) -> Result<String, HttpResponse> {
-    if let Some(auth_header) = req.headers().get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-                let token_data = decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret(SECRET_KEY.as_ref()),
-                    &Validation::default(),
-                );
-
-                return match token_data {
-                    Ok(data) => Ok(data.claims.sub),
-                    Err(_) => Err(HttpResponse::Unauthorized().body("Invalid token")),
-                };
-            }
-        }
+// Troca ids de usuário pelos emails, que é como os eleitores aparecem na cadeia
+async fn resolve_user_ids<C: GenericClient>(client: &C, user_ids: &[i32]) -> Result<Vec<String>, AppError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = client.query(
+        "SELECT id, email FROM users WHERE id = ANY($1)",
+        &[&user_ids],
+    ).await?;
+
+    let found: HashMap<i32, String> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+    user_ids.iter()
+        .map(|id| found.get(id).cloned().ok_or(AppError::UserNotFound))
+        .collect()
+}
+
+// Adiciona título, opções, forma de votação, sigilo, restrição de eleitores, status e janela de votação da eleição à resposta
+fn add_election_details(response: &mut serde_json::Value, blockchain: &Blockchain, election_id: &str) {
+    if let Some(election) = blockchain.elections.get(election_id) {
+        response["slug"] = serde_json::json!(election.slug);
+        response["created_at"] = serde_json::json!(election.created_at);
+        response["title"] = serde_json::json!(election.title);
+        response["description"] = serde_json::json!(election.description);
+        response["options"] = serde_json::json!(election.options);
+    }
+    response["secret_ballot"] = serde_json::json!(blockchain.secret_ballots.contains(election_id));
+    response["open_to_all"] = serde_json::json!(blockchain.eligibility.get(election_id).is_none_or(Eligibility::is_open));
+    response["voting_method"] = serde_json::json!(blockchain.methods.get(election_id).copied().unwrap_or_default());
+    if let Some(max_selections) = blockchain.max_selections.get(election_id) {
+        response["max_selections"] = serde_json::json!(max_selections);
+    }
+    if let Some(schedule) = blockchain.schedules.get(election_id) {
+        response["status"] = serde_json::json!(blockchain.election_status(election_id));
+        response["starts_at"] = serde_json::json!(schedule.starts_at);
+        response["ends_at"] = serde_json::json!(schedule.ends_at);
+        response["closed_at"] = serde_json::json!(schedule.closed_at);
     }
-    Err(HttpResponse::Unauthorized().body("Missing or malformed Authorization header"))
 }
 
 #[post("/election")]
 async fn handle_post_create_election(
-    req: HttpRequest,
+    user: AuthenticatedUser,
     blockchain: web::Data<SharedBlockchain>,
+    pool: web::Data<Pool>,
     web::Json(payload): web::Json<CreateElectionPayload>,
-) -> HttpResponse {
-    let creator_id = match extract_user_id_from_token(&req) {
-        Ok(id) => id,
-        Err(resp) => return resp,
+) -> Result<HttpResponse, AppError> {
+    // Só managers e admins organizam eleições
+    user.require_role(&[Role::Manager, Role::Admin])?;
+    let creator_id = user.email;
+
+    let max_vote_options = config().max_vote_options;
+    if payload.vote_options.len() > max_vote_options {
+        return Err(AppError::TooManyOptions(max_vote_options));
+    }
+
+    // A conexão é devolvida antes de entrar na fila de escrita
+    let client = pool.get().await?;
+    let mut eligible_voters = payload.eligible_voters;
+    eligible_voters.extend(resolve_user_ids(&client, &payload.eligible_user_ids).await?);
+    drop(client);
+    let eligibility = Eligibility::new(eligible_voters, payload.eligible_domains)
+        .map_err(AppError::InvalidPayload)?;
+
+    let writer = blockchain.writer().await?;
+    let blockchain = writer.read().await;
+
+    let election = NewElection {
+        slug: non_empty(payload.slug),
+        title: non_empty(payload.title),
+        description: non_empty(payload.description),
+        options: payload.vote_options.into_iter().map(VoteOption::from).collect(),
+        starts_at: payload.starts_at,
+        ends_at: payload.ends_at,
+        voting_method: payload.voting_method,
+        max_selections: payload.max_selections,
+        eligibility,
+        secret_ballot: payload.secret_ballot,
     };
 
-    if payload.vote_options.len() > 20 {
-        return HttpResponse::BadRequest().json("Cannot create more than 20 vote options");
-    }
+    let block = blockchain.create_election(election, creator_id)?;
+    let (election_id, slug) = match &block.payload {
+        BlockPayload::ElectionCreated { election_id, slug, .. } => (election_id.clone(), slug.clone()),
+        _ => return Err(AppError::Internal("create_election returned an unexpected block".to_string())),
+    };
 
-    let mut blockchain = blockchain.lock().unwrap();
+    // Solta a leitura para que outros pedidos leiam a cadeia enquanto o
+    // bloco é salvo; o `writer` continua impedindo outras escritas
+    drop(blockchain);
 
-    match blockchain.create_election(payload.election_id.clone(), payload.vote_options.clone(), creator_id) {
-        Ok(_) => HttpResponse::Ok().json("Election created successfully"),
-        Err(err) => HttpResponse::BadRequest().json(err),
-    }
+    append_block(&pool, &writer, block, None).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Election created successfully",
+        "election_id": election_id,
+        "slug": slug,
+    })))
 }
 
 #[post("/vote")]
 async fn handle_post_vote(
-    req: HttpRequest,
+    user: AuthenticatedUser,
     blockchain: web::Data<SharedBlockchain>,
+    pool: web::Data<Pool>,
     web::Json(payload): web::Json<VotePayload>,
-) -> HttpResponse {
-    let voter_id = match extract_user_id_from_token(&req) {
-        Ok(id) => id,
-        Err(resp) => return resp,
+) -> Result<HttpResponse, AppError> {
+    let voter_id = user.email;
+
+    let ballot = match (payload.vote_option_ids, payload.vote_option_id) {
+        (Some(vote_option_ids), _) => vote_option_ids,
+        (None, Some(vote_option_id)) => vec![vote_option_id],
+        (None, None) => return Err(AppError::MissingParameter("vote_option_id")),
     };
 
-    let mut blockchain = blockchain.lock().unwrap();
+    let writer = blockchain.writer().await?;
+    let blockchain = writer.read().await;
+
+    let election_id = blockchain.resolve_election_id(&payload.election_id).unwrap_or(payload.election_id);
+    let (block, ballot_secret) = blockchain.add_vote_operation(voter_id.clone(), election_id.clone(), ballot)?;
+
+    let receipt = Receipt::for_block(&block);
+    drop(blockchain);
+
+    let ballot_secret = match ballot_secret {
+        Some(ballot_secret) => ballot_secret,
+        None => {
+            append_block(&pool, &writer, block, None).await?;
+            return Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Vote added successfully",
+                "receipt": receipt,
+            })));
+        }
+    };
+
+    // Voto secreto: a participação e a cédula são gravadas juntas, ou nenhuma.
+    // Por isso um administrador do banco consegue ligá-las (ver migration 0005).
+    append_block(&pool, &writer, block, Some((election_id, voter_id))).await?;
+
+    // O segredo não fica guardado em lugar nenhum: sem ele o eleitor não reencontra o voto
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Vote added successfully",
+        "receipt": receipt,
+        "ballot_secret": ballot_secret,
+    })))
+}
+
+#[post("/election/{election_id}/close")]
+async fn handle_post_close_election(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    pool: web::Data<Pool>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let requester_id = user.email;
+
+    let writer = blockchain.writer().await?;
+    let blockchain = writer.read().await;
+
+    let election_id = path.into_inner();
+    let election_id = blockchain.resolve_election_id(&election_id).unwrap_or(election_id);
+    let block = blockchain.close_election(election_id, requester_id)?;
+    drop(blockchain);
+
+    append_block(&pool, &writer, block, None).await?;
+    Ok(HttpResponse::Ok().json("Election closed successfully"))
+}
+
+#[get("/election/{election_id}/eligible-voters")]
+async fn handle_get_eligible_voters(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let election_id = path.into_inner();
+
+    let blockchain = blockchain.read().await;
+
+    let election_id = blockchain.resolve_election_id(&election_id).ok_or(AppError::ElectionNotFound)?;
+
+    // A lista de aptos só é visível para quem criou a eleição
+    if !blockchain.is_creator(&user.email, &election_id) {
+        return Err(AppError::NotElectionCreator);
+    }
+
+    let eligibility = blockchain.eligibility.get(&election_id).cloned().unwrap_or_default();
+    drop(blockchain);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "election_id": election_id,
+        "open_to_all": eligibility.is_open(),
+        "voters": eligibility.voters,
+        "domains": eligibility.domains,
+    })))
+}
+
+#[post("/election/{election_id}/eligible-voters")]
+async fn handle_post_eligible_voters(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    pool: web::Data<Pool>,
+    path: web::Path<String>,
+    web::Json(payload): web::Json<EligibleVotersPayload>,
+) -> Result<HttpResponse, AppError> {
+    let requester_id = user.email;
+
+    let client = pool.get().await?;
+    let mut voters = payload.voters;
+    voters.extend(resolve_user_ids(&client, &payload.user_ids).await?);
+    drop(client);
+
+    let writer = blockchain.writer().await?;
+    let blockchain = writer.read().await;
+
+    let election_id = path.into_inner();
+    let election_id = blockchain.resolve_election_id(&election_id).unwrap_or(election_id);
+    let block = blockchain.change_eligibility(election_id, requester_id, voters, Vec::new())?;
+    drop(blockchain);
+
+    append_block(&pool, &writer, block, None).await?;
+    Ok(HttpResponse::Ok().json("Eligible voters added successfully"))
+}
+
+#[delete("/election/{election_id}/eligible-voters/{voter_id}")]
+async fn handle_delete_eligible_voter(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    pool: web::Data<Pool>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let requester_id = user.email;
+    let (election_id, voter_id) = path.into_inner();
+
+    let writer = blockchain.writer().await?;
+    let blockchain = writer.read().await;
 
-    match blockchain.add_vote_operation(voter_id, payload.election_id.clone(), payload.vote_option_id.clone()) {
-        Ok(_) => HttpResponse::Ok().json("Vote added successfully"),
-        Err(err) => HttpResponse::BadRequest().json(err),
+    let election_id = blockchain.resolve_election_id(&election_id).unwrap_or(election_id);
+    let block = blockchain.change_eligibility(election_id, requester_id, Vec::new(), vec![voter_id])?;
+    drop(blockchain);
+
+    append_block(&pool, &writer, block, None).await?;
+    Ok(HttpResponse::Ok().json("Eligible voter removed successfully"))
+}
+
+// Pacote de auditoria de uma eleição encerrada, para entregar a auditores externos
+#[get("/election/{election_id}/export")]
+async fn handle_get_export_election(
+    user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let election_id = path.into_inner();
+
+    let blockchain = blockchain.read().await;
+
+    let election_id = blockchain.resolve_election_id(&election_id).ok_or(AppError::ElectionNotFound)?;
+    let status = blockchain.election_status(&election_id).ok_or(AppError::ElectionNotFound)?;
+
+    if !blockchain.is_creator(&user.email, &election_id) && user.role != Role::Admin {
+        return Err(AppError::NotElectionCreator);
     }
+
+    if status != ElectionStatus::Closed {
+        return Err(AppError::InvalidElection("Only closed elections can be exported".to_string()));
+    }
+
+    let bundle = blockchain
+        .export_bundle(&election_id)
+        .ok_or_else(|| AppError::Internal(format!("Could not export election {}", election_id)))?;
+    drop(blockchain);
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.audit.jsonl\"", election_id)))
+        .body(bundle))
 }
 
 #[get("/elections")]
 async fn handle_get_all_elections(
     req: HttpRequest,
+    user: AuthenticatedUser,
     blockchain: web::Data<SharedBlockchain>,
-    query: web::Query<ElectionQuery>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     println!("Received request to handle_get_all_elections");
 
-    let blockchain = blockchain.lock().unwrap();
+    let blockchain = blockchain.read().await;
 
-    let voter_id_extract = match extract_user_id_from_token(&req) {
-        Ok(id) => id,
-        Err(resp) => return resp,
-    };
+    let voter_id_extract = user.email;
 
     // Buscar todas as eleições em que o usuário participou
-    let elections = blockchain.get_elections_by_user(&voter_id_extract);
+    let mut elections = blockchain.get_elections_by_user(&voter_id_extract);
+
+    // Votos secretos só aparecem com o segredo de cada cédula
+    elections.extend(
+        ballot_secrets(&req)
+            .iter()
+            .filter_map(|ballot_secret| blockchain.get_ballot_by_secret(ballot_secret)),
+    );
 
     // Inicializar vetor de respostas
     let mut responses = Vec::new();
@@ -121,39 +426,102 @@ async fn handle_get_all_elections(
         if let Some(election) = blockchain.elections.get(&election_id) {
             let mut response = serde_json::json!({
                 "election_id": election_id,
-                "vote_options": election.iter().cloned().collect::<Vec<_>>(), // Assumindo que election é um HashSet de opções de voto
+                "vote_options": election.option_ids(),
             });
 
             // Verificar se o vote_option_id está na lista de opções de voto da eleição
-            if election.contains(&vote_option_id) {
+            if election.has_option(&vote_option_id) {
                 response["user_vote"] = serde_json::json!(vote_option_id);
             } else {
                 response["user_vote"] = serde_json::Value::Null;
             }
 
+            add_election_details(&mut response, &blockchain, &election_id);
             responses.push(response);
         } else {
             println!("Election not found for id: {:?}", election_id);
         }
     }
+    drop(blockchain);
 
     // Retornar a resposta com todas as eleições que o usuário participou
-    HttpResponse::Ok().json(responses)
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+// Busca entre todas as eleições, paginada por cursor: a resposta traz
+// `next_cursor`, que é repassado na próxima chamada até vir null
+#[get("/elections/browse")]
+async fn handle_get_browse_elections(
+    _user: AuthenticatedUser,
+    blockchain: web::Data<SharedBlockchain>,
+    query: web::Query<BrowseQuery>,
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+
+    let status = match query.status.as_deref() {
+        None => None,
+        Some("draft") | Some("scheduled") => Some(ElectionStatus::Scheduled),
+        Some("open") => Some(ElectionStatus::Open),
+        Some("closed") => Some(ElectionStatus::Closed),
+        Some(_) => return Err(AppError::InvalidPayload("status must be draft, open or closed".to_string())),
+    };
+
+    let cursor = match &query.cursor {
+        Some(cursor) => Some(cursor.parse::<usize>().map_err(|_| AppError::InvalidPayload("Invalid cursor".to_string()))?),
+        None => None,
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(AppError::InvalidPayload(format!("limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let filter = ElectionFilter {
+        status,
+        creator: query.creator.map(|creator| creator.trim().to_string()),
+        created_after: query.created_after.map(u128::from),
+        created_before: query.created_before.map(u128::from),
+        query: query.q,
+    };
+
+    let blockchain = blockchain.read().await;
+
+    // O cursor é a posição de uma eleição; fora do índice ele não veio de uma página anterior
+    if cursor.is_some_and(|cursor| cursor >= blockchain.index.len()) {
+        return Err(AppError::InvalidPayload("Invalid cursor".to_string()));
+    }
+
+    let (election_ids, next_cursor) = blockchain.browse_elections(&filter, query.sort, cursor, limit);
+
+    let mut elections = Vec::new();
+    for election_id in election_ids {
+        if let Some(election) = blockchain.elections.get(&election_id) {
+            let mut response = serde_json::json!({
+                "election_id": election_id,
+                "vote_options": election.option_ids(),
+            });
+            add_election_details(&mut response, &blockchain, &election_id);
+            elections.push(response);
+        }
+    }
+    drop(blockchain);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "elections": elections,
+        "next_cursor": next_cursor.map(|cursor| cursor.to_string()),
+    })))
 }
 
 #[get("/user/created-elections")]
 async fn handle_get_elections_created_by_user(
-    req: HttpRequest,
+    user: AuthenticatedUser,
     blockchain: web::Data<SharedBlockchain>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     println!("Received request to handle_get_elections_created_by_user");
 
-    let blockchain = blockchain.lock().unwrap();
+    let blockchain = blockchain.read().await;
 
-    let creator_id_extract = match extract_user_id_from_token(&req) {
-        Ok(id) => id,
-        Err(resp) => return resp,
-    };
+    let creator_id_extract = user.email;
 
     // Buscar todas as eleições em que o usuário participou
     let elections = blockchain.get_elections_created_by_user(&creator_id_extract);
@@ -162,47 +530,73 @@ async fn handle_get_elections_created_by_user(
     let mut responses = Vec::new();
 
     // Para cada eleição em que o usuário participou, criar a resposta correspondente
-    for (election_id) in elections {
+    for election_id in elections {
         if let Some(election) = blockchain.elections.get(&election_id) {
             let mut response = serde_json::json!({
                 "election_id": election_id,
-                "vote_options": election.iter().cloned().collect::<Vec<_>>(), // Assumindo que election é um HashSet de opções de voto
+                "vote_options": election.option_ids(),
             });
 
             // Verificar se o vote_option_id está na lista de opções de voto da eleição
-            // if election.contains(&vote_option_id) {
+            // if election.has_option(&vote_option_id) {
             //     response["user_vote"] = serde_json::json!(vote_option_id);
             // } else {
             //     response["user_vote"] = serde_json::Value::Null;
             // }
 
+            add_election_details(&mut response, &blockchain, &election_id);
             responses.push(response);
         } else {
             println!("Election not found for id: {:?}", election_id);
         }
     }
+    drop(blockchain);
 
     // Retornar a resposta com todas as eleições que o usuário participou
-    HttpResponse::Ok().json(responses)
+    Ok(HttpResponse::Ok().json(responses))
 }
 
 
+// Segredos de cédulas secretas enviados no cabeçalho `X-Ballot-Secret`, que
+// pode se repetir ou trazer vários separados por vírgula. Não vão na query
+// string para não ficarem em logs de acesso e no histórico do navegador.
+fn ballot_secrets(req: &HttpRequest) -> Vec<String> {
+    req.headers()
+        .get_all("X-Ballot-Secret")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| secret.to_string())
+        .collect()
+}
+
 #[get("/election")]
 async fn handle_get_election(
     req: HttpRequest,
     blockchain: web::Data<SharedBlockchain>,
     query: web::Query<ElectionQuery>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     println!("Received request to handle_get_election");
 
-    let blockchain = blockchain.lock().unwrap();
+    let ballot_secret = ballot_secrets(&req).into_iter().next();
+
+    // O token é conferido antes de pegar o ledger, que não fica travado durante o await
+    let voter_id = if ballot_secret.is_none() && query.voter_id.is_some() {
+        Some(authenticate(&req).await?.email)
+    } else {
+        None
+    };
 
-    // println!("elections filter: {:?}", blockchain.get_results_election(&query.election_id));
+    let blockchain = blockchain.read().await;
 
     // Verifique se o election_id foi fornecido na query
     if let Some(election_id) = &query.election_id {
         println!("Query parameter election_id: {:?}", election_id);
 
+        // Aceita o id ou o slug da eleição
+        let election_id = &blockchain.resolve_election_id(election_id).unwrap_or_else(|| election_id.clone());
+
         // Procure a eleição pelo ID
         if let Some(election) = blockchain.elections.get(election_id) {
             println!("Found election: {:?}", election);
@@ -210,19 +604,21 @@ async fn handle_get_election(
             // Inicialize o objeto de resposta
             let mut response = serde_json::json!({
                 "election_id": election_id,
-                "vote_options": election.iter().cloned().collect::<Vec<_>>(), // Assumindo que election é um HashSet de opções de voto
+                "vote_options": election.option_ids(),
             });
 
-            // Se voter_id for fornecido, recupere o voto do usuário
-            if let Some(voter_id) = &query.voter_id {
-                let voter_id_extract = match extract_user_id_from_token(&req) {
-                    Ok(id) => id,
-                    Err(resp) => return resp,
+            // Com o segredo da cédula, recupere o voto secreto do usuário
+            if let Some(ballot_secret) = &ballot_secret {
+                response["user_vote"] = match blockchain.get_ballot_by_secret(ballot_secret) {
+                    Some((ballot_election, vote_option_id)) if ballot_election == *election_id => serde_json::json!(vote_option_id),
+                    _ => serde_json::Value::Null,
                 };
-                println!("Found vote id: {:?}", blockchain.get_votes_by_user(&voter_id_extract, election_id));
-                if let Some((_, vote_option_id)) = blockchain.get_votes_by_user(&voter_id_extract, election_id) {
+            } else if let Some(voter_id_extract) = &voter_id {
+                // Se voter_id for fornecido, recupere o voto do usuário
+                println!("Found vote id: {:?}", blockchain.get_votes_by_user(voter_id_extract, election_id));
+                if let Some((_, vote_option_id)) = blockchain.get_votes_by_user(voter_id_extract, election_id) {
                     // Verificar se o vote_option_id está na lista de opções de voto da eleição
-                    if election.contains(&vote_option_id) {
+                    if election.has_option(&vote_option_id) {
                         response["user_vote"] = serde_json::json!(vote_option_id);
                     } else {
                         response["user_vote"] = serde_json::Value::Null;
@@ -235,14 +631,17 @@ async fn handle_get_election(
                 response["user_vote"] = serde_json::Value::Null;
             }
 
-            HttpResponse::Ok().json(response)
+            add_election_details(&mut response, &blockchain, election_id);
+            drop(blockchain);
+
+            Ok(HttpResponse::Ok().json(response))
         } else {
             println!("Election not found for id: {:?}", election_id);
-            HttpResponse::NotFound().json("Election not found")
+            Err(AppError::ElectionNotFound)
         }
     } else {
         println!("Missing election_id query parameter");
-        HttpResponse::BadRequest().json("Missing election_id query parameter")
+        Err(AppError::MissingParameter("election_id"))
     }
 }
 
@@ -250,58 +649,73 @@ async fn handle_get_election(
 async fn handle_get_results_election(
     blockchain: web::Data<SharedBlockchain>,
     query: web::Query<ResultsQuery>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
 
     
-    let blockchain = blockchain.lock().unwrap();
+    let blockchain = blockchain.read().await;
     
     // Verifique se o election_id foi fornecido na query
     if let Some(election_id) = &query.election_id {
-        println!("elections filter: {:?}", blockchain.get_results_election(election_id));
-        println!("Query parameter election_id: {:?}", election_id);
+        // Aceita o id ou o slug da eleição
+        let election_id = &blockchain.resolve_election_id(election_id).ok_or(AppError::ElectionNotFound)?;
 
-        let elections = blockchain.get_results_election(election_id);
+        println!("Query parameter election_id: {:?}", election_id);
 
         // Inicializar vetor de respostas
         let mut responses = Vec::new();
 
-        let mut votes: HashMap<String, Vec<String>> = HashMap::new();
+        // Contagem mantida a cada voto, sem reler as cédulas
+        let running_tally = blockchain.running_tally(election_id).cloned().unwrap_or_default();
+        let status = blockchain.election_status(election_id);
+        let voting_method = blockchain.methods.get(election_id).copied().unwrap_or_default();
+        let options = blockchain.elections.get(election_id).map(|election| election.option_ids());
 
-        for (election_id, vote_option_id) in elections {
+        // A apuração rodada a rodada é feita sobre uma cópia das cédulas, sem a trava
+        let ballots = match (voting_method, &options) {
+            (VotingMethod::RankedChoice, Some(_)) => blockchain.get_ballots_election(election_id),
+            _ => Vec::new(),
+        };
+        let election_id = election_id.clone();
+        drop(blockchain);
 
-            if let Some(election) = blockchain.elections.get(&election_id){
-                let mut response = serde_json::json!({
-                    "election_id": election_id,
-                    "vote_options": election.iter().cloned().collect::<Vec<_>>(), // Assumindo que election é um HashSet de opções de voto
-                });
-                if !votes.contains_key(&vote_option_id) {
-                    for vote_option in election {
-                        votes.insert(String::from(vote_option), Vec::new());
-                    }
-                }
-            } else {
-                println!("Election not found for id: {:?}", election_id);
-            }
-            
-           
-            println!("vote 1: {:?}", votes);
-            votes.entry(vote_option_id.clone()).or_insert(Vec::new()).push(String::from(vote_option_id));
-            println!("vote 2: {:?}", votes);
-        }
         // Retornar a resposta com todas as eleições que o usuário participou
         let mut response = serde_json::json!({
             "election_id": election_id,
         });
 
-        for (key, value) in &votes {
-            println!("{}: {}", key, value.len());
-            response[key] = serde_json::json!(value.len());
+        // Enquanto a eleição não for encerrada, a apuração é apenas parcial
+        response["status"] = serde_json::json!(status);
+        response["provisional"] = serde_json::json!(status != Some(ElectionStatus::Closed));
+
+        // As contagens ficam num objeto à parte: o id de uma opção pode ser
+        // igual ao nome de qualquer outro campo da resposta
+        response["counts"] = serde_json::json!(running_tally.first_choices);
+
+        // Eleições ranqueadas: as contagens acima são de primeira preferência;
+        // a apuração completa vem rodada a rodada.
+        // Eleições por aprovação: cada opção conta todas as cédulas que a marcaram.
+        response["voting_method"] = serde_json::json!(voting_method);
+        if let Some(mut options) = options {
+            options.sort();
+            match voting_method {
+                VotingMethod::RankedChoice => {
+                    let result = instant_runoff(&options, &ballots);
+                    response["rounds"] = serde_json::json!(result.rounds);
+                    response["winner"] = serde_json::json!(result.winner);
+                }
+                VotingMethod::Approval => {
+                    response["counts"] = serde_json::json!(running_tally.marks);
+                    response["winners"] = serde_json::json!(leaders(&running_tally.marks));
+                }
+                VotingMethod::Plurality => {}
+            }
         }
         responses.push(response);
-        HttpResponse::Ok().json(responses)
+
+        Ok(HttpResponse::Ok().json(responses))
     } else {
         println!("Missing election_id query parameter");
-        HttpResponse::BadRequest().json("Missing election_id query parameter")
+        Err(AppError::MissingParameter("election_id"))
     }
 }
 
@@ -309,6 +723,12 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg
         .service(handle_post_create_election)
         .service(handle_post_vote)
+        .service(handle_post_close_election)
+        .service(handle_get_eligible_voters)
+        .service(handle_post_eligible_voters)
+        .service(handle_delete_eligible_voter)
+        .service(handle_get_export_election)
+        .service(handle_get_browse_elections)
         .service(handle_get_all_elections)
         .service(handle_get_election)
         .service(handle_get_results_election)
@@ -1,38 +1,203 @@
+use std::time::Duration;
+use deadpool_postgres::{GenericClient, Manager, ManagerConfig, Pool, RecyclingMethod, Runtime};
 use tokio_postgres::{ NoTls, Error as PostgresError};
-use crate::constants::constants::DB_URL;
+use tokio_postgres::types::Json;
+use ed25519_dalek::SigningKey;
+use crate::config::config::Config;
+use crate::blockchain::block::{Block, BlockPayload};
+use crate::blockchain::blockchain::Blockchain;
+use crate::blockchain::ledger::{Ledger, LedgerWriter};
+use crate::errors::errors::AppError;
 
-pub async fn set_database() -> Result<(), PostgresError> {
-    // Connect to the database
-    let (client, connection) = tokio_postgres::connect(DB_URL, NoTls).await?;
+// Cria o pool de conexões compartilhado por todos os handlers.
+// As conexões são abertas sob demanda, até `db_pool_max_size`.
+pub fn create_pool(config: &Config) -> Result<Pool, String> {
+    let pg_config = config.database_url
+        .parse::<tokio_postgres::Config>()
+        .map_err(|e| format!("Invalid DATABASE_URL: {}", e))?;
 
-    // Spawn a new task to run the connection in the background
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {:?}", e);
-        }
+    let manager = Manager::from_config(pg_config, NoTls, ManagerConfig {
+        recycling_method: RecyclingMethod::Fast,
     });
 
-    // Create tables
-    client.batch_execute(
-        "CREATE TABLE IF NOT EXISTS users (
-            id SERIAL PRIMARY KEY,
-            name VARCHAR NOT NULL,
-            email VARCHAR NOT NULL UNIQUE,
-            password VARCHAR NOT NULL,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )"
+    let timeout = Some(Duration::from_secs(config.db_pool_timeout_secs));
+    Pool::builder(manager)
+        .max_size(config.db_pool_max_size)
+        .wait_timeout(timeout)
+        .create_timeout(timeout)
+        .recycle_timeout(timeout)
+        .runtime(Runtime::Tokio1)
+        .build()
+        .map_err(|e| format!("Could not create database pool: {}", e))
+}
+
+async fn read_blocks<C: GenericClient>(client: &C) -> Result<Vec<Block>, String> {
+    let rows = client.query(
+        "SELECT index, timestamp, payload, previous_hash, hash, signature FROM blocks ORDER BY index",
+        &[],
+    ).await.map_err(|e| format!("Database error: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let index: i64 = row.get(0);
+            let timestamp: i64 = row.get(1);
+            let Json(payload): Json<BlockPayload> = row.try_get(2)
+                .map_err(|e| format!("Block {} has an unreadable payload: {}", index, e))?;
+            Ok(Block {
+                index: index as u64,
+                timestamp: timestamp as u128,
+                payload,
+                previous_hash: row.get(3),
+                hash: row.get(4),
+                signature: row.get(5),
+            })
+        })
+        .collect()
+}
+
+// Carrega a blockchain salva no banco, reaplicando os blocos em ordem.
+// Se ainda não houver blocos, cria uma nova cadeia e persiste o bloco gênesis.
+pub async fn load_blockchain(pool: &Pool, signing_key: SigningKey) -> Result<Blockchain, String> {
+    let client = pool.get().await.map_err(|e| format!("Database error: {}", e))?;
+
+    let blocks = read_blocks(&client).await?;
+
+    if blocks.is_empty() {
+        let blockchain = Blockchain::new(signing_key);
+        insert_block(&client, &blockchain.chain[0]).await.map_err(|e| format!("Database error: {}", e))?;
+        return Ok(blockchain);
+    }
+
+    let mut blockchain = Blockchain::from_stored(blocks, signing_key)?;
+
+    // Quem já votou nas eleições secretas não aparece na cadeia
+    let rows = client.query(
+        "SELECT election_id, voter_id FROM election_participation",
+        &[],
+    ).await.map_err(|e| format!("Database error: {}", e))?;
+    for row in rows {
+        blockchain.record_participation(row.get(0), row.get(1));
+    }
+
+    Ok(blockchain)
+}
+
+pub async fn insert_block<C: GenericClient>(client: &C, block: &Block) -> Result<(), PostgresError> {
+    client.execute(
+        "INSERT INTO blocks (index, timestamp, payload, previous_hash, hash, signature) VALUES ($1, $2, $3, $4, $5, $6)",
+        &[
+            &(block.index as i64),
+            &(block.timestamp as i64),
+            &Json(&block.payload),
+            &block.previous_hash,
+            &block.hash,
+            &block.signature,
+        ],
     ).await?;
 
-    client.batch_execute(
-        "CREATE TABLE IF NOT EXISTS sessions (
-            id SERIAL PRIMARY KEY,
-            user_id INTEGER NOT NULL,
-            token VARCHAR NOT NULL UNIQUE,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (user_id) REFERENCES users (id)
-        )"
+    Ok(())
+}
+
+// Caminho de toda escrita na cadeia: o bloco montado pelo `writer` é
+// conferido contra a cadeia, gravado no banco e só então anexado, de modo que
+// um bloco que a quebraria nunca chega ao banco e a cadeia em memória nunca
+// tem um bloco que o banco não tem. Com `participation` (eleição, eleitor), a
+// participação numa eleição secreta é gravada na mesma transação que a cédula.
+// A conexão só é pega aqui, já com o `writer`: quem espera na fila de escrita
+// não segura uma conexão do pool.
+pub async fn append_block(pool: &Pool, writer: &LedgerWriter<'_>, block: Block, participation: Option<(String, String)>) -> Result<(), AppError> {
+    writer.check(&block).await?;
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    if let Some((election_id, voter_id)) = &participation {
+        if let Err(err) = insert_participation(&transaction, election_id, voter_id).await {
+            return match err.as_db_error() {
+                Some(pq_err) if pq_err.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION => Err(AppError::AlreadyVoted),
+                _ => Err(AppError::Database(err)),
+            };
+        }
+    }
+    insert_block(&transaction, &block).await?;
+    transaction.commit().await?;
+
+    writer.commit(block).await?;
+    if let Some((election_id, voter_id)) = participation {
+        writer.record_participation(election_id, voter_id).await;
+    }
+    Ok(())
+}
+
+// Dá o papel de admin ao usuário com esse email. Retorna false se ele não existir.
+pub async fn grant_admin(pool: &Pool, email: &str) -> Result<bool, String> {
+    let client = pool.get().await.map_err(|e| format!("Database error: {}", e))?;
+
+    let updated = client.execute(
+        "UPDATE users SET role = 'admin', updated_at = CURRENT_TIMESTAMP WHERE email = $1",
+        &[&email],
+    ).await.map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(updated > 0)
+}
+
+// Marca que o eleitor votou numa eleição secreta; um segundo registro viola a chave primária
+pub async fn insert_participation<C: GenericClient>(client: &C, election_id: &str, voter_id: &str) -> Result<(), PostgresError> {
+    client.execute(
+        "INSERT INTO election_participation (election_id, voter_id) VALUES ($1, $2)",
+        &[&election_id, &voter_id],
     ).await?;
 
     Ok(())
 }
+
+// Assina os blocos gravados antes de a assinatura existir. A cadeia precisa
+// estar íntegra: blocos adulterados nunca recebem assinatura.
+pub async fn sign_chain(pool: &Pool, signing_key: SigningKey) -> Result<usize, String> {
+    let mut client = pool.get().await.map_err(|e| format!("Database error: {}", e))?;
+    let transaction = client.transaction().await.map_err(|e| format!("Database error: {}", e))?;
+
+    let mut blockchain = Blockchain::replay(read_blocks(&transaction).await?, signing_key);
+    if let Err(err) = blockchain.verify_unsigned() {
+        return Err(format!("Stored chain is invalid: {}", err));
+    }
+
+    let signed = blockchain.sign_unsigned_blocks();
+    for block in &signed {
+        transaction.execute(
+            "UPDATE blocks SET signature = $1 WHERE index = $2",
+            &[&block.signature, &(block.index as i64)],
+        ).await.map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    transaction.commit().await.map_err(|e| format!("Database error: {}", e))?;
+    Ok(signed.len())
+}
+
+// Registra o encerramento, com a raiz de Merkle das cédulas, das eleições que
+// terminaram pelo horário sem que o criador as encerrasse. Retorna quantas
+// foram encerradas.
+pub async fn close_ended_elections(pool: &Pool, ledger: &Ledger) -> Result<usize, String> {
+    // Na maior parte das rodadas não há nada a encerrar e o escritor nem é disputado
+    if ledger.read().await.ended_without_close_record().is_empty() {
+        return Ok(0);
+    }
+
+    let writer = ledger.writer().await.map_err(|e| e.to_string())?;
+
+    // A trava de leitura é solta antes de cada `commit`, que precisa da de escrita
+    let ended = writer.read().await.ended_without_close_record();
+
+    let mut closed = 0;
+    for election_id in ended {
+        let block = writer.read().await.close_ended_election(&election_id);
+        let block = match block {
+            Some(block) => block,
+            None => continue,
+        };
+
+        append_block(pool, &writer, block, None).await.map_err(|e| e.to_string())?;
+        closed += 1;
+    }
+
+    Ok(closed)
+}
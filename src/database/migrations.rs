@@ -0,0 +1,187 @@
+use deadpool_postgres::{Client, Pool};
+use sha2::{Sha256, Digest};
+
+// Migrations embutidas no binário, em ordem de versão. Uma migration já
+// aplicada nunca deve ser editada: o checksum gravado em schema_migrations
+// é conferido a cada execução. Para mudar o esquema, crie uma nova versão.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: include_str!("../../migrations/0001_initial_schema.up.sql"),
+        down: include_str!("../../migrations/0001_initial_schema.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "session_metadata",
+        up: include_str!("../../migrations/0002_session_metadata.up.sql"),
+        down: include_str!("../../migrations/0002_session_metadata.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "refresh_tokens",
+        up: include_str!("../../migrations/0003_refresh_tokens.up.sql"),
+        down: include_str!("../../migrations/0003_refresh_tokens.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "user_roles",
+        up: include_str!("../../migrations/0004_user_roles.up.sql"),
+        down: include_str!("../../migrations/0004_user_roles.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "election_participation",
+        up: include_str!("../../migrations/0005_election_participation.up.sql"),
+        down: include_str!("../../migrations/0005_election_participation.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "block_signatures",
+        up: include_str!("../../migrations/0006_block_signatures.up.sql"),
+        down: include_str!("../../migrations/0006_block_signatures.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "legacy_vote_blocks",
+        up: include_str!("../../migrations/0007_legacy_vote_blocks.up.sql"),
+        down: include_str!("../../migrations/0007_legacy_vote_blocks.down.sql"),
+    },
+];
+
+// Chave do advisory lock que impede duas instâncias de migrarem ao mesmo tempo
+const MIGRATION_LOCK_KEY: i64 = 7_245_118_302;
+
+impl Migration {
+    pub fn checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.up.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+// Aplica as migrations pendentes, cada uma na sua própria transação.
+// Retorna as versões aplicadas nesta execução.
+pub async fn run_migrations(pool: &Pool) -> Result<Vec<i64>, String> {
+    let mut client = pool.get().await.map_err(|e| format!("Database error: {}", e))?;
+
+    lock(&client).await?;
+    let result = apply_pending(&mut client).await;
+    unlock(&client).await?;
+    result
+}
+
+// Desfaz as últimas `steps` migrations aplicadas, da mais recente para a mais antiga
+pub async fn rollback_migrations(pool: &Pool, steps: usize) -> Result<Vec<i64>, String> {
+    let mut client = pool.get().await.map_err(|e| format!("Database error: {}", e))?;
+
+    lock(&client).await?;
+    let result = roll_back(&mut client, steps).await;
+    unlock(&client).await?;
+    result
+}
+
+async fn apply_pending(client: &mut Client) -> Result<Vec<i64>, String> {
+    let applied = applied_migrations(client).await?;
+
+    let mut newly_applied = Vec::new();
+    for migration in MIGRATIONS {
+        if applied.iter().any(|(version, _)| *version == migration.version) {
+            continue;
+        }
+
+        let transaction = client.transaction().await.map_err(|e| format!("Database error: {}", e))?;
+        transaction.batch_execute(migration.up).await
+            .map_err(|e| format!("Migration {} ({}) failed: {}", migration.version, migration.name, e))?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)",
+            &[&migration.version, &migration.name, &migration.checksum()],
+        ).await.map_err(|e| format!("Database error: {}", e))?;
+        transaction.commit().await.map_err(|e| format!("Database error: {}", e))?;
+
+        println!("Applied migration {} ({})", migration.version, migration.name);
+        newly_applied.push(migration.version);
+    }
+
+    Ok(newly_applied)
+}
+
+async fn roll_back(client: &mut Client, steps: usize) -> Result<Vec<i64>, String> {
+    let applied = applied_migrations(client).await?;
+
+    let mut rolled_back = Vec::new();
+    for (version, _) in applied.iter().rev().take(steps) {
+        // `applied_migrations` já garantiu que toda versão aplicada é conhecida
+        let migration = MIGRATIONS.iter().find(|migration| migration.version == *version).unwrap();
+
+        let transaction = client.transaction().await.map_err(|e| format!("Database error: {}", e))?;
+        transaction.batch_execute(migration.down).await
+            .map_err(|e| format!("Rollback of migration {} ({}) failed: {}", migration.version, migration.name, e))?;
+        transaction.execute(
+            "DELETE FROM schema_migrations WHERE version = $1",
+            &[&migration.version],
+        ).await.map_err(|e| format!("Database error: {}", e))?;
+        transaction.commit().await.map_err(|e| format!("Database error: {}", e))?;
+
+        println!("Rolled back migration {} ({})", migration.version, migration.name);
+        rolled_back.push(migration.version);
+    }
+
+    Ok(rolled_back)
+}
+
+async fn lock(client: &Client) -> Result<(), String> {
+    client.execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_KEY]).await
+        .map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+async fn unlock(client: &Client) -> Result<(), String> {
+    client.execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_KEY]).await
+        .map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+// Versões e checksums já aplicados, validados contra as migrations deste binário
+async fn applied_migrations(client: &Client) -> Result<Vec<(i64, String)>, String> {
+    client.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            checksum VARCHAR NOT NULL,
+            applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )"
+    ).await.map_err(|e| format!("Database error: {}", e))?;
+
+    let rows = client.query(
+        "SELECT version, checksum FROM schema_migrations ORDER BY version",
+        &[],
+    ).await.map_err(|e| format!("Database error: {}", e))?;
+
+    let applied: Vec<(i64, String)> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+    validate(&applied)?;
+    Ok(applied)
+}
+
+// Toda migration registrada no banco precisa existir no binário com o mesmo conteúdo
+fn validate(applied: &[(i64, String)]) -> Result<(), String> {
+    for (version, checksum) in applied {
+        match MIGRATIONS.iter().find(|migration| migration.version == *version) {
+            None => return Err(format!(
+                "Database has migration {} applied, which this build does not know about", version
+            )),
+            Some(migration) if migration.checksum() != *checksum => return Err(format!(
+                "Migration {} ({}) was modified after being applied: checksum mismatch", version, migration.name
+            )),
+            _ => {}
+        }
+    }
+    Ok(())
+}
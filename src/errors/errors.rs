@@ -0,0 +1,179 @@
+use std::fmt;
+use actix_web::{http::StatusCode, HttpRequest, HttpResponse, ResponseError};
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use tokio_postgres::Error as PostgresError;
+use deadpool_postgres::PoolError;
+
+// Erro único da aplicação. Toda resposta de erro tem o formato
+// {"code": "ELECTION_NOT_FOUND", "message": "Election not found"},
+// e o frontend deve decidir pelo `code`, nunca pela mensagem.
+#[derive(Debug)]
+pub enum AppError {
+    MissingToken,
+    InvalidToken,
+    InvalidCredentials,
+    InvalidRefreshToken,
+    RefreshTokenReused,
+    Forbidden,
+    UserNotFound,
+    LastAdmin,
+    EmailAlreadyExists,
+    InvalidPayload(String),
+    MissingParameter(&'static str),
+    ElectionNotFound,
+    ReceiptNotFound,
+    SlugTaken,
+    InvalidElection(String),
+    TooManyOptions(usize),
+    OptionInvalid,
+    InvalidBallot(String),
+    AlreadyVoted,
+    NotEligible,
+    ElectionNotStarted,
+    ElectionClosed,
+    ElectionAlreadyClosed,
+    NotElectionCreator,
+    Database(PostgresError),
+    ServiceUnavailable,
+    LedgerUnavailable,
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::MissingToken => "MISSING_TOKEN",
+            AppError::InvalidToken => "INVALID_TOKEN",
+            AppError::InvalidCredentials => "INVALID_CREDENTIALS",
+            AppError::InvalidRefreshToken => "INVALID_REFRESH_TOKEN",
+            AppError::RefreshTokenReused => "REFRESH_TOKEN_REUSED",
+            AppError::Forbidden => "FORBIDDEN",
+            AppError::UserNotFound => "USER_NOT_FOUND",
+            AppError::LastAdmin => "LAST_ADMIN",
+            AppError::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+            AppError::InvalidPayload(_) => "INVALID_PAYLOAD",
+            AppError::MissingParameter(_) => "MISSING_PARAMETER",
+            AppError::ElectionNotFound => "ELECTION_NOT_FOUND",
+            AppError::ReceiptNotFound => "RECEIPT_NOT_FOUND",
+            AppError::SlugTaken => "SLUG_TAKEN",
+            AppError::InvalidElection(_) => "ELECTION_INVALID",
+            AppError::TooManyOptions(_) => "TOO_MANY_OPTIONS",
+            AppError::OptionInvalid => "OPTION_INVALID",
+            AppError::InvalidBallot(_) => "BALLOT_INVALID",
+            AppError::AlreadyVoted => "ALREADY_VOTED",
+            AppError::NotEligible => "NOT_ELIGIBLE",
+            AppError::ElectionNotStarted => "ELECTION_NOT_STARTED",
+            AppError::ElectionClosed => "ELECTION_CLOSED",
+            AppError::ElectionAlreadyClosed => "ELECTION_ALREADY_CLOSED",
+            AppError::NotElectionCreator => "NOT_ELECTION_CREATOR",
+            AppError::Database(_) => "DATABASE_ERROR",
+            AppError::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            AppError::LedgerUnavailable => "LEDGER_UNAVAILABLE",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::MissingToken => write!(f, "Missing or malformed Authorization header"),
+            AppError::InvalidToken => write!(f, "Invalid token"),
+            AppError::InvalidCredentials => write!(f, "Invalid credentials"),
+            AppError::InvalidRefreshToken => write!(f, "Invalid or expired refresh token"),
+            AppError::RefreshTokenReused => write!(f, "Refresh token was already used; all sessions from it were revoked"),
+            AppError::Forbidden => write!(f, "Your role does not allow this action"),
+            AppError::UserNotFound => write!(f, "User not found"),
+            AppError::LastAdmin => write!(f, "Cannot remove the last admin"),
+            AppError::EmailAlreadyExists => write!(f, "Email already exists"),
+            AppError::InvalidPayload(reason) => write!(f, "Invalid request: {}", reason),
+            AppError::MissingParameter(name) => write!(f, "Missing {} parameter", name),
+            AppError::ElectionNotFound => write!(f, "Election not found"),
+            AppError::ReceiptNotFound => write!(f, "No ballot recorded with this block hash"),
+            AppError::SlugTaken => write!(f, "Slug is already in use by another election"),
+            AppError::InvalidElection(reason) => write!(f, "{}", reason),
+            AppError::TooManyOptions(max) => write!(f, "Cannot create more than {} vote options", max),
+            AppError::OptionInvalid => write!(f, "Vote option does not exist in this election"),
+            AppError::InvalidBallot(reason) => write!(f, "{}", reason),
+            AppError::AlreadyVoted => write!(f, "Voter has already voted in this election"),
+            AppError::NotEligible => write!(f, "You are not eligible to vote in this election"),
+            AppError::ElectionNotStarted => write!(f, "Election has not started yet"),
+            AppError::ElectionClosed => write!(f, "Election is closed"),
+            AppError::ElectionAlreadyClosed => write!(f, "Election is already closed"),
+            AppError::NotElectionCreator => write!(f, "Only the election creator can do this"),
+            AppError::ServiceUnavailable => write!(f, "Service temporarily unavailable, try again"),
+            AppError::LedgerUnavailable => write!(f, "Voting is unavailable until the server is restarted"),
+            // Detalhes internos nunca vão para a resposta, só para o log
+            AppError::Database(_) | AppError::Internal(_) => write!(f, "Internal server error"),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::MissingToken
+            | AppError::InvalidToken
+            | AppError::InvalidCredentials
+            | AppError::InvalidRefreshToken
+            | AppError::RefreshTokenReused => StatusCode::UNAUTHORIZED,
+            AppError::NotElectionCreator
+            | AppError::Forbidden
+            | AppError::NotEligible => StatusCode::FORBIDDEN,
+            AppError::ElectionNotFound
+            | AppError::UserNotFound
+            | AppError::ReceiptNotFound => StatusCode::NOT_FOUND,
+            AppError::EmailAlreadyExists
+            | AppError::SlugTaken
+            | AppError::AlreadyVoted
+            | AppError::ElectionAlreadyClosed
+            | AppError::LastAdmin => StatusCode::CONFLICT,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ServiceUnavailable | AppError::LedgerUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AppError::Database(e) => eprintln!("Database error: {:?}", e),
+            AppError::Internal(e) => eprintln!("Internal error: {}", e),
+            _ => {}
+        }
+
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+        }))
+    }
+}
+
+impl From<PostgresError> for AppError {
+    fn from(e: PostgresError) -> Self {
+        AppError::Database(e)
+    }
+}
+
+// Esgotar o pool (ninguém devolveu conexão dentro do timeout) vira 503
+impl From<PoolError> for AppError {
+    fn from(e: PoolError) -> Self {
+        match e {
+            PoolError::Timeout(_) => AppError::ServiceUnavailable,
+            PoolError::Backend(e) => AppError::Database(e),
+            e => AppError::Internal(format!("Database pool error: {}", e)),
+        }
+    }
+}
+
+// Corpos JSON, query strings e parâmetros de rota malformados também respondem no formato padrão
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::InvalidPayload(err.to_string()).into()
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::InvalidPayload(err.to_string()).into()
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::InvalidPayload(err.to_string()).into()
+}
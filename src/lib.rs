@@ -0,0 +1,6 @@
+#![allow(clippy::module_inception)]
+
+// Núcleo da blockchain, compartilhado pelo servidor (main.rs) e pelo
+// verificador offline de pacotes de auditoria (bin/verify_bundle.rs)
+pub mod blockchain;
+pub mod errors;
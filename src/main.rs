@@ -1,17 +1,29 @@
+#![allow(clippy::module_inception)]
+
 mod controllers;
 mod utils;
 mod models;
 mod database;
 mod constants;
-mod blockchain;
+mod config;
+mod cli;
+mod auth;
 
+use rust_crud_api::{blockchain, errors};
 use actix_cors::Cors;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
-use std::sync::{Arc, Mutex};
-use database::database::set_database;
+use std::sync::Arc;
+use std::time::Duration;
+use blockchain::ledger::Ledger;
+use database::database::{create_pool, load_blockchain, grant_admin, sign_chain, close_ended_elections};
+use database::migrations::{run_migrations, rollback_migrations};
+use cli::cli::{parse_args, Command, USAGE};
 use controllers::users::*;
 use controllers::votation::configure as votation_configure;
-use blockchain::blockchain::{Blockchain, SharedBlockchain};
+use controllers::chain::configure as chain_configure;
+use controllers::admin::configure as admin_configure;
+use errors::errors::{json_error_handler, path_error_handler, query_error_handler};
+use constants::constants::AUTO_CLOSE_INTERVAL_SECS;
 
 #[macro_use]
 extern crate serde_derive;
@@ -22,31 +34,155 @@ async fn hello_world() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let command = match parse_args() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return Err(std::io::Error::other("Invalid arguments"));
+        }
+    };
+
+    // Gerar uma chave não depende de configuração nenhuma
+    if let Command::GenerateSigningKey = command {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        println!("{}", hex::encode(signing_key.to_bytes()));
+        return Ok(());
+    }
+
+    // Carrega a configuração antes de qualquer outra coisa
+    let config = match config::config::init() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            return Err(std::io::Error::other("Invalid configuration"));
+        }
+    };
+
+    // Pool de conexões único, compartilhado pelos handlers
+    let pool = match create_pool(config) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(std::io::Error::other("Database pool setup failed"));
+        }
+    };
+
+    // Comandos de migration rodam sem subir o servidor HTTP
+    if let Command::Rollback(steps) = command {
+        return match rollback_migrations(&pool, steps).await {
+            Ok(versions) => {
+                println!("Rolled back {} migration(s)", versions.len());
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Error rolling back migrations: {}", e);
+                Err(std::io::Error::other("Rollback failed"))
+            }
+        };
+    }
+
     // Set up the database
-    if let Err(e) = set_database().await {
-        eprintln!("Error setting up the database: {:?}", e);
-        return Err(std::io::Error::new(std::io::ErrorKind::Other, "Database setup failed"));
+    if let Err(e) = run_migrations(&pool).await {
+        eprintln!("Error setting up the database: {}", e);
+        return Err(std::io::Error::other("Database setup failed"));
     }
 
-    let blockchain = Arc::new(Mutex::new(Blockchain::new()));
+    if let Command::Migrate = command {
+        return Ok(());
+    }
+
+    // Promove um usuário já cadastrado a admin; é assim que o primeiro admin é criado
+    if let Command::MakeAdmin(email) = command {
+        return match grant_admin(&pool, &email).await {
+            Ok(true) => {
+                println!("{} is now an admin", email);
+                Ok(())
+            }
+            Ok(false) => {
+                eprintln!("No user registered with email {}", email);
+                Err(std::io::Error::other("User not found"))
+            }
+            Err(e) => {
+                eprintln!("Error granting admin role: {}", e);
+                Err(std::io::Error::other("Grant admin failed"))
+            }
+        };
+    }
+
+    if let Command::SignChain = command {
+        return match sign_chain(&pool, config.signing_key.clone()).await {
+            Ok(signed) => {
+                println!("Signed {} block(s)", signed);
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Error signing the blockchain: {}", e);
+                Err(std::io::Error::other("Sign chain failed"))
+            }
+        };
+    }
+
+    // Carrega a blockchain persistida; uma cadeia adulterada impede o servidor de subir
+    let blockchain = match load_blockchain(&pool, config.signing_key.clone()).await {
+        Ok(blockchain) => Arc::new(Ledger::new(blockchain)),
+        Err(e) => {
+            eprintln!("Error loading the blockchain: {}", e);
+            return Err(std::io::Error::other("Blockchain load failed"));
+        }
+    };
+
+    // Eleições que terminam pelo horário também precisam da raiz de Merkle
+    // publicada na cadeia, mesmo que ninguém as encerre
+    {
+        let blockchain = blockchain.clone();
+        let pool = pool.clone();
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(Duration::from_secs(AUTO_CLOSE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                match close_ended_elections(&pool, &blockchain).await {
+                    Ok(0) => {}
+                    Ok(closed) => println!("Recorded the close of {} election(s) that ended by schedule", closed),
+                    Err(e) => eprintln!("Error closing ended elections: {}", e),
+                }
+            }
+        });
+    }
 
     HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
+        let mut cors = Cors::default();
+        if config.allows_any_origin() {
+            cors = cors.allow_any_origin();
+        } else {
+            for origin in &config.cors_origins {
+                cors = cors.allowed_origin(origin);
+            }
+        }
+        let cors = cors
             .allow_any_method()
             .allow_any_header()
             .max_age(3600);
 
         App::new()
-            .app_data(web::Data::new(blockchain.clone())) 
+            .app_data(web::Data::new(blockchain.clone()))
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
             .wrap(cors)
             .route("/", web::get().to(hello_world))
             .route("/signup", web::post().to(handle_post_signup))
             .route("/login", web::post().to(handle_login_request))
             .route("/token", web::post().to(handle_auth_request))
+            .route("/token/refresh", web::post().to(handle_refresh_token))
+            .route("/logout", web::post().to(handle_logout))
+            .route("/logout-all", web::post().to(handle_logout_all))
+            .route("/sessions", web::get().to(handle_get_sessions))
             .configure(votation_configure)
+            .configure(chain_configure)
+            .configure(admin_configure)
     })
-    .bind("0.0.0.0:8080")?
+    .bind(config.bind_address)?
     .run()
     .await
 }
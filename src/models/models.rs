@@ -1,12 +1,60 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Papel do usuário. Admins gerenciam usuários, managers (e admins) criam
+// eleições, e qualquer usuário pode votar.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Manager,
+    #[default]
+    Voter,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Manager => "manager",
+            Role::Voter => "voter",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "admin" => Ok(Role::Admin),
+            "manager" => Ok(Role::Manager),
+            "voter" => Ok(Role::Voter),
+            _ => Err(format!("Unknown role: {}", value)),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub iat: usize, // Timestamp de emissão
     pub exp: usize, // Timestamp de expiração
+    #[serde(default)]
+    pub jti: String, // Id único do token, para que duas sessões nunca tenham o mesmo JWT
+    #[serde(default)]
+    pub role: Role,
 }
 
 //Model: USer struct with id, name, email
 #[derive(Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct User {
     pub id: Option<i32>,
     pub name: String,
@@ -28,8 +76,39 @@ pub struct LoginData {
     pub password: String,
 }
 
+//Model: RefreshData struct with the opaque refresh token
+#[derive(Serialize, Deserialize)]
+pub struct RefreshData {
+    pub refresh_token: String,
+}
+
 //Model: SignupData struct with name, email, password
 #[derive(Serialize, Deserialize)]
 pub struct AuthData {
     pub token: String,
-}
\ No newline at end of file
+}
+
+//Model: active session, as listed in GET /sessions (timestamps in ms)
+#[derive(Serialize)]
+pub struct SessionInfo {
+    pub id: i32,
+    pub created_at: Option<i64>,
+    pub expires_at: Option<i64>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub current: bool,
+}
+//Model: user as listed in GET /admin/users
+#[derive(Serialize)]
+pub struct UserInfo {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub role: Role,
+}
+
+//Model: RoleData struct with the new role of a user
+#[derive(Serialize, Deserialize)]
+pub struct RoleData {
+    pub role: Role,
+}